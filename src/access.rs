@@ -0,0 +1,63 @@
+/// one bit per address
+const WORDS: usize = 0x10000 / 64;
+
+/// which addresses the program has read and which it has written, for
+/// telling apart tables, variables and scratch ram
+#[derive(Debug, Clone)]
+pub struct AccessMap {
+    read: Box<[u64; WORDS]>,
+    written: Box<[u64; WORDS]>,
+}
+
+impl Default for AccessMap {
+    fn default() -> Self {
+        Self {
+            read: Box::new([0; WORDS]),
+            written: Box::new([0; WORDS]),
+        }
+    }
+}
+
+fn get(bits: &[u64; WORDS], addr: u16) -> bool {
+    bits[addr as usize / 64] & (1 << (addr % 64)) != 0
+}
+
+fn set(bits: &mut [u64; WORDS], addr: u16) {
+    bits[addr as usize / 64] |= 1 << (addr % 64);
+}
+
+impl AccessMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mark_read(&mut self, addr: u16) {
+        set(&mut self.read, addr);
+    }
+
+    pub fn mark_written(&mut self, addr: u16) {
+        set(&mut self.written, addr);
+    }
+
+    pub fn was_read(&self, addr: u16) -> bool {
+        get(&self.read, addr)
+    }
+
+    pub fn was_written(&self, addr: u16) -> bool {
+        get(&self.written, addr)
+    }
+
+    /// output buffers and dead stores
+    pub fn written_not_read(&self) -> Vec<u16> {
+        (0..=0xffff)
+            .filter(|&addr| self.was_written(addr) && !self.was_read(addr))
+            .collect()
+    }
+
+    /// code and constant tables
+    pub fn read_not_written(&self) -> Vec<u16> {
+        (0..=0xffff)
+            .filter(|&addr| self.was_read(addr) && !self.was_written(addr))
+            .collect()
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+/// the register order every opcode encodes them in
+const REGISTERS: [&str; 8] = ["B", "C", "D", "E", "H", "L", "M", "A"];
+/// conditions in opcode order, for Jcc, Ccc and Rcc
+const CONDITIONS: [&str; 8] = ["NZ", "Z", "NC", "C", "PO", "PE", "P", "M"];
+
+#[derive(Debug, PartialEq)]
+pub enum AsmError {
+    UnknownInstruction { line: usize, text: String },
+    BadOperand { line: usize, operand: String },
+    UnknownLabel { line: usize, label: String },
+    DuplicateLabel { line: usize, label: String },
+}
+
+impl std::fmt::Display for AsmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AsmError::UnknownInstruction { line, text } => {
+                write!(f, "line {}: unknown instruction {}", line, text)
+            }
+            AsmError::BadOperand { line, operand } => {
+                write!(f, "line {}: bad operand {}", line, operand)
+            }
+            AsmError::UnknownLabel { line, label } => {
+                write!(f, "line {}: unknown label {}", line, label)
+            }
+            AsmError::DuplicateLabel { line, label } => {
+                write!(f, "line {}: label {} defined twice", line, label)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// what follows the opcode byte
+#[derive(Debug)]
+enum Operand {
+    None,
+    Byte(u8),
+    /// a number or a label, resolved once every label is known
+    Word(String),
+}
+
+fn number(text: &str) -> Option<u16> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+fn register(text: &str) -> Option<u8> {
+    let text = text.to_ascii_uppercase();
+    REGISTERS.iter().position(|r| *r == text).map(|i| i as u8)
+}
+
+/// B, D, H and then SP, or PSW for PUSH and POP
+fn pair(text: &str, last: &str) -> Option<u8> {
+    match text.to_ascii_uppercase().as_str() {
+        "B" => Some(0),
+        "D" => Some(1),
+        "H" => Some(2),
+        text if text == last => Some(3),
+        _ => None,
+    }
+}
+
+/// opcode and operand of one instruction
+fn encode(mnemonic: &str, operands: &[&str], line: usize) -> Result<(u8, Operand), AsmError> {
+    let bad = |operand: &str| AsmError::BadOperand {
+        line,
+        operand: operand.to_string(),
+    };
+    let byte = |text: &str| -> Result<Operand, AsmError> {
+        number(text)
+            .and_then(|value| u8::try_from(value).ok())
+            .map(Operand::Byte)
+            .ok_or_else(|| bad(text))
+    };
+    let reg = |text: &str| register(text).ok_or_else(|| bad(text));
+    let rp = |text: &str, last: &str| pair(text, last).ok_or_else(|| bad(text));
+    let word = |text: &str| Operand::Word(text.to_string());
+
+    let alu = ["ADD", "ADC", "SUB", "SBB", "ANA", "XRA", "ORA", "CMP"];
+    let alu_immediate = ["ADI", "ACI", "SUI", "SBI", "ANI", "XRI", "ORI", "CPI"];
+    let condition = |prefix: &str| {
+        mnemonic
+            .strip_prefix(prefix)
+            .and_then(|cc| CONDITIONS.iter().position(|c| *c == cc))
+            .map(|i| (i as u8) << 3)
+    };
+
+    let encoded = match (mnemonic, operands) {
+        ("NOP", []) => (0x00, Operand::None),
+        ("RLC", []) => (0x07, Operand::None),
+        ("RRC", []) => (0x0f, Operand::None),
+        ("RAL", []) => (0x17, Operand::None),
+        ("RAR", []) => (0x1f, Operand::None),
+        ("DAA", []) => (0x27, Operand::None),
+        ("CMA", []) => (0x2f, Operand::None),
+        ("STC", []) => (0x37, Operand::None),
+        ("CMC", []) => (0x3f, Operand::None),
+        ("HLT", []) => (0x76, Operand::None),
+        ("RET", []) => (0xc9, Operand::None),
+        ("XTHL", []) => (0xe3, Operand::None),
+        ("PCHL", []) => (0xe9, Operand::None),
+        ("XCHG", []) => (0xeb, Operand::None),
+        ("DI", []) => (0xf3, Operand::None),
+        ("SPHL", []) => (0xf9, Operand::None),
+        ("EI", []) => (0xfb, Operand::None),
+        ("MOV", [dst, src]) => {
+            let (dst, src) = (reg(dst)?, reg(src)?);
+            // MOV M, M is where HLT sits
+            if dst == 6 && src == 6 {
+                return Err(bad("M"));
+            }
+            (0x40 | dst << 3 | src, Operand::None)
+        }
+        ("MVI", [r, value]) => (0x06 | reg(r)? << 3, byte(value)?),
+        ("INR", [r]) => (0x04 | reg(r)? << 3, Operand::None),
+        ("DCR", [r]) => (0x05 | reg(r)? << 3, Operand::None),
+        ("LXI", [p, value]) => (0x01 | rp(p, "SP")? << 4, word(value)),
+        ("DAD", [p]) => (0x09 | rp(p, "SP")? << 4, Operand::None),
+        ("INX", [p]) => (0x03 | rp(p, "SP")? << 4, Operand::None),
+        ("DCX", [p]) => (0x0b | rp(p, "SP")? << 4, Operand::None),
+        ("PUSH", [p]) => (0xc5 | rp(p, "PSW")? << 4, Operand::None),
+        ("POP", [p]) => (0xc1 | rp(p, "PSW")? << 4, Operand::None),
+        ("STAX", [p]) => match rp(p, "")? {
+            p @ (0 | 1) => (0x02 | p << 4, Operand::None),
+            _ => return Err(bad(p)),
+        },
+        ("LDAX", [p]) => match rp(p, "")? {
+            p @ (0 | 1) => (0x0a | p << 4, Operand::None),
+            _ => return Err(bad(p)),
+        },
+        ("SHLD", [addr]) => (0x22, word(addr)),
+        ("LHLD", [addr]) => (0x2a, word(addr)),
+        ("STA", [addr]) => (0x32, word(addr)),
+        ("LDA", [addr]) => (0x3a, word(addr)),
+        ("JMP", [addr]) => (0xc3, word(addr)),
+        ("CALL", [addr]) => (0xcd, word(addr)),
+        ("OUT", [port]) => (0xd3, byte(port)?),
+        ("IN", [port]) => (0xdb, byte(port)?),
+        ("RST", [n]) => match number(n) {
+            Some(n @ 0..=7) => (0xc7 | (n as u8) << 3, Operand::None),
+            _ => return Err(bad(n)),
+        },
+        (_, [r]) if alu.contains(&mnemonic) => {
+            let i = alu.iter().position(|m| *m == mnemonic).unwrap() as u8;
+            (0x80 | i << 3 | reg(r)?, Operand::None)
+        }
+        (_, [value]) if alu_immediate.contains(&mnemonic) => {
+            let i = alu_immediate.iter().position(|m| *m == mnemonic).unwrap() as u8;
+            (0xc6 | i << 3, byte(value)?)
+        }
+        (_, [addr]) if condition("J").is_some() => (0xc2 | condition("J").unwrap(), word(addr)),
+        (_, [addr]) if condition("C").is_some() => (0xc4 | condition("C").unwrap(), word(addr)),
+        (_, []) if condition("R").is_some() => (0xc0 | condition("R").unwrap(), Operand::None),
+        _ => {
+            return Err(AsmError::UnknownInstruction {
+                line,
+                text: format!("{} {}", mnemonic, operands.join(", "))
+                    .trim()
+                    .to_string(),
+            })
+        }
+    };
+    Ok(encoded)
+}
+
+/// assembles 8080 source into bytes starting at address 0. one instruction
+/// per line, `;` starts a comment and `name:` defines a label. numbers are
+/// decimal or `0x` hex, word operands can also be labels
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    let mut labels = HashMap::new();
+    let mut instructions = Vec::new();
+    let mut addr: u16 = 0;
+
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let mut text = line.split(';').next().unwrap_or_default().trim();
+        if let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            if labels.insert(label.to_string(), addr).is_some() {
+                return Err(AsmError::DuplicateLabel {
+                    line: line_number,
+                    label: label.to_string(),
+                });
+            }
+            text = rest.trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let operands: Vec<&str> = match rest.trim() {
+            "" => Vec::new(),
+            rest => rest.split(',').map(str::trim).collect(),
+        };
+        let (opcode, operand) = encode(&mnemonic.to_ascii_uppercase(), &operands, line_number)?;
+        addr = addr.wrapping_add(match operand {
+            Operand::None => 1,
+            Operand::Byte(_) => 2,
+            Operand::Word(_) => 3,
+        });
+        instructions.push((line_number, opcode, operand));
+    }
+
+    let mut bytes = Vec::new();
+    for (line, opcode, operand) in instructions {
+        bytes.push(opcode);
+        match operand {
+            Operand::None => {}
+            Operand::Byte(value) => bytes.push(value),
+            Operand::Word(text) => {
+                let value = match (
+                    number(&text),
+                    text.starts_with(|c: char| c.is_ascii_digit()),
+                ) {
+                    (Some(value), _) => value,
+                    (None, true) => {
+                        return Err(AsmError::BadOperand {
+                            line,
+                            operand: text,
+                        })
+                    }
+                    (None, false) => *labels.get(&text).ok_or(AsmError::UnknownLabel {
+                        line,
+                        label: text.clone(),
+                    })?,
+                };
+                bytes.extend(value.to_le_bytes());
+            }
+        }
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use macroquad::audio::{
+    load_sound, play_sound, play_sound_once, stop_sound, PlaySoundParams, Sound,
+};
+
+use crate::invaders::{InvadersIo, SoundEvent};
+
+/// where the front-end looks for the samples, named like the mame set
+pub const SAMPLE_DIR: &str = "./rom/space-invaders/samples";
+
+/// file name of the sample for each sound
+pub fn sample(sound: SoundEvent) -> &'static str {
+    match sound {
+        SoundEvent::Ufo => "0.wav",
+        SoundEvent::Shot => "1.wav",
+        SoundEvent::PlayerDeath => "2.wav",
+        SoundEvent::InvaderDeath => "3.wav",
+        SoundEvent::Fleet1 => "4.wav",
+        SoundEvent::Fleet2 => "5.wav",
+        SoundEvent::Fleet3 => "6.wav",
+        SoundEvent::Fleet4 => "7.wav",
+        SoundEvent::UfoHit => "8.wav",
+    }
+}
+
+/// plays the samples for what the game wrote to the sound ports
+pub struct Mixer {
+    sounds: HashMap<SoundEvent, Sound>,
+    ufo: bool,
+}
+
+impl Mixer {
+    /// loads every sample found in `dir`. missing ones stay silent
+    pub async fn load(dir: &str) -> Self {
+        let mut sounds = HashMap::new();
+        for sound in SoundEvent::ALL {
+            if let Ok(loaded) = load_sound(&format!("{}/{}", dir, sample(sound))).await {
+                sounds.insert(sound, loaded);
+            }
+        }
+        Self { sounds, ufo: false }
+    }
+
+    pub fn update(&mut self, io: &mut InvadersIo) {
+        for sound in io.take_sounds() {
+            // the ufo is a loop, handled below
+            if sound == SoundEvent::Ufo {
+                continue;
+            }
+            if let Some(sample) = self.sounds.get(&sound) {
+                play_sound_once(sample);
+            }
+        }
+
+        let ufo = io.sound_on(SoundEvent::Ufo);
+        if ufo != self.ufo {
+            if let Some(sample) = self.sounds.get(&SoundEvent::Ufo) {
+                match ufo {
+                    true => play_sound(
+                        sample,
+                        PlaySoundParams {
+                            looped: true,
+                            volume: 1.,
+                        },
+                    ),
+                    false => stop_sound(sample),
+                }
+            }
+            self.ufo = ufo;
+        }
+    }
+}
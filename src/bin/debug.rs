@@ -0,0 +1,339 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use intel_8080_emu::cpu::Cpu8080;
+use intel_8080_emu::invaders::{load_rom, SpaceInvaders, DEFAULT_ROM};
+use intel_8080_emu::state::Rewind;
+
+/// `continue` gives up after this many steps without hitting a breakpoint
+/// so a runaway program doesn't lock up the terminal
+const CONTINUE_LIMIT: usize = 10_000_000;
+/// lines the history pane keeps, more than a terminal is tall
+const HISTORY_LEN: usize = 256;
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Step(usize),
+    Back(usize),
+    Continue,
+    Breakpoint(u16),
+    Delete(u16),
+    Goto(u16),
+    Memory(u16),
+    List(Option<u16>),
+    Poke(u16, u8),
+    Quit,
+}
+
+fn parse_number(text: &str) -> Result<u16> {
+    let value = match text.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16)?,
+        None => text.parse()?,
+    };
+    Ok(value)
+}
+
+fn parse_command(line: &str) -> Result<Command> {
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or("s");
+    let mut addr = || -> Result<u16> {
+        let arg = words
+            .next()
+            .ok_or_else(|| anyhow!("{} expects an address", command))?;
+        parse_number(arg)
+    };
+    Ok(match command {
+        "s" | "step" => match words.next() {
+            Some(count) => Command::Step(count.parse()?),
+            None => Command::Step(1),
+        },
+        "r" | "back" => match words.next() {
+            Some(count) => Command::Back(count.parse()?),
+            None => Command::Back(1),
+        },
+        "c" | "continue" => Command::Continue,
+        "b" | "break" => Command::Breakpoint(addr()?),
+        "d" | "delete" => Command::Delete(addr()?),
+        "g" | "goto" => Command::Goto(addr()?),
+        "m" | "memory" => Command::Memory(addr()?),
+        "l" | "list" => match words.next() {
+            Some(addr) => Command::List(Some(parse_number(addr)?)),
+            None => Command::List(None),
+        },
+        "p" | "poke" => {
+            let addr = addr()?;
+            let value = words
+                .next()
+                .ok_or_else(|| anyhow!("{} expects a value", command))?;
+            Command::Poke(addr, u8::try_from(parse_number(value)?)?)
+        }
+        "q" | "quit" => Command::Quit,
+        _ => bail!("unknown command: {}", command),
+    })
+}
+
+struct Debugger {
+    machine: SpaceInvaders,
+    breakpoints: BTreeSet<u16>,
+    /// first address of the memory pane
+    memory: u16,
+    /// first address of the code pane, `None` follows pc
+    list: Option<u16>,
+    input: String,
+    status: String,
+    /// the last `HISTORY_LEN` lines executed, each with the step count
+    /// after it so stepping back can drop them again. oldest first
+    history: VecDeque<(u64, String)>,
+}
+
+impl Debugger {
+    /// one step on the board, its lines go to the history pane
+    fn step(&mut self) {
+        let pc = self.machine.cpu.pc;
+        self.machine.step();
+        let cpu = &mut self.machine.cpu;
+        for text in cpu.history.drain(..) {
+            if self.history.len() == HISTORY_LEN {
+                self.history.pop_front();
+            }
+            self.history
+                .push_back((cpu.steps, format!("{:#06x} {}", pc, text)));
+        }
+    }
+
+    /// returns false once the user asked to quit
+    fn run(&mut self, command: Command) -> bool {
+        match command {
+            Command::Step(count) => {
+                for _ in 0..count {
+                    self.step();
+                }
+                self.status = format!("stepped {}", count);
+            }
+            Command::Back(count) => {
+                let undone = (0..count)
+                    .take_while(|_| self.machine.cpu.step_back())
+                    .count();
+                let steps = self.machine.cpu.steps;
+                self.history.retain(|(step, _)| *step <= steps);
+                self.status = format!("stepped back {}", undone);
+            }
+            Command::Continue => {
+                self.step();
+                let mut steps = 1;
+                while !self.breakpoints.contains(&self.machine.cpu.pc) && steps < CONTINUE_LIMIT {
+                    self.step();
+                    steps += 1;
+                }
+                self.status = match self.breakpoints.contains(&self.machine.cpu.pc) {
+                    true => format!("breakpoint at {:#06x}", self.machine.cpu.pc),
+                    false => format!("stopped after {} steps", steps),
+                };
+            }
+            Command::Breakpoint(addr) => {
+                self.breakpoints.insert(addr);
+                self.status = format!("breakpoint set at {:#06x}", addr);
+            }
+            Command::Delete(addr) => {
+                self.status = match self.breakpoints.remove(&addr) {
+                    true => format!("breakpoint at {:#06x} removed", addr),
+                    false => format!("no breakpoint at {:#06x}", addr),
+                };
+            }
+            Command::Goto(addr) => {
+                self.machine.cpu.pc = addr;
+                self.status = format!("pc = {:#06x}", addr);
+            }
+            Command::Memory(addr) => self.memory = addr,
+            Command::List(addr) => self.list = addr,
+            Command::Poke(addr, value) => {
+                self.machine.cpu.poke(addr, value);
+                self.status = format!("{:#06x} = {:#04x}", addr, value);
+            }
+            Command::Quit => return false,
+        }
+        true
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [top, memory, prompt] = Layout::vertical([
+            Constraint::Min(10),
+            Constraint::Length(18),
+            Constraint::Length(3),
+        ])
+        .areas(frame.area());
+        let [registers, stack, code, history] = Layout::horizontal([
+            Constraint::Length(24),
+            Constraint::Length(17),
+            Constraint::Min(30),
+            Constraint::Min(30),
+        ])
+        .areas(top);
+
+        let cpu = &self.machine.cpu;
+        let flag = |name: &'static str, set: bool| match set {
+            true => name,
+            false => "-",
+        };
+        let lines = vec![
+            Line::from(format!("a  {:#04x}", cpu.a)),
+            Line::from(format!("bc {:#06x}", cpu.bc())),
+            Line::from(format!("de {:#06x}", cpu.de())),
+            Line::from(format!("hl {:#06x}", cpu.hl())),
+            Line::from(format!("sp {:#06x}", cpu.sp)),
+            Line::from(format!("pc {:#06x}", cpu.pc)),
+            Line::from(format!(
+                "{} {} {} {} {}",
+                flag("z", cpu.z),
+                flag("s", cpu.s),
+                flag("p", cpu.p),
+                flag("cy", cpu.cy),
+                flag("ac", cpu.ac)
+            )),
+            Line::from(format!(
+                "ei {} halt {}",
+                cpu.interrupt as u8, cpu.halt as u8
+            )),
+        ];
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("registers")),
+            registers,
+        );
+
+        let lines: Vec<Line> = cpu
+            .stack_view(stack.height.saturating_sub(2) as usize)
+            .into_iter()
+            .map(|(addr, word)| Line::from(format!("{:#06x} {:#06x}", addr, word)))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("stack")),
+            stack,
+        );
+
+        let rows = code.height.saturating_sub(2) as usize;
+        let listing = match self.list {
+            Some(start) => cpu
+                .list_from(start, rows)
+                .into_iter()
+                .map(|(addr, text)| (addr, text, addr == cpu.pc))
+                .collect(),
+            // the line at pc comes on top of `before` and `after`
+            None => cpu.disasm_window(rows / 3, (rows - rows / 3).saturating_sub(1)),
+        };
+        let lines: Vec<Line> = listing
+            .into_iter()
+            .map(|(addr, text, current)| {
+                let marker = match self.breakpoints.contains(&addr) {
+                    true => '*',
+                    false => ' ',
+                };
+                let line = Line::from(format!("{}{:#06x} {}", marker, addr, text));
+                match current {
+                    true => line.style(Style::default().add_modifier(Modifier::REVERSED)),
+                    false => line,
+                }
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("code")),
+            code,
+        );
+
+        let rows = history.height.saturating_sub(2) as usize;
+        let lines: Vec<Line> = self
+            .history
+            .iter()
+            .skip(self.history.len().saturating_sub(rows))
+            .map(|(_, text)| Line::from(text.as_str()))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("history")),
+            history,
+        );
+
+        frame.render_widget(
+            Paragraph::new(cpu.hexdump(self.memory, 0x100))
+                .block(Block::default().borders(Borders::ALL).title("memory")),
+            memory,
+        );
+
+        frame.render_widget(
+            Paragraph::new(format!("> {}", self.input)).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(self.status.as_str()),
+            ),
+            prompt,
+        );
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Esc => return Ok(()),
+                KeyCode::Char(c) => self.input.push(c),
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                // an empty line steps once, like most debuggers
+                KeyCode::Enter => {
+                    let line = std::mem::take(&mut self.input);
+                    match parse_command(&line) {
+                        Ok(command) => {
+                            if !self.run(command) {
+                                return Ok(());
+                            }
+                        }
+                        Err(err) => self.status = err.to_string(),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let path = std::env::args().nth(1).unwrap_or(DEFAULT_ROM.to_string());
+
+    let mut cpu = Cpu8080::new();
+    load_rom(&mut cpu, &path).with_context(|| {
+        format!(
+            "no rom at {}, pass another path as the first argument",
+            path
+        )
+    })?;
+    // 64 snapshots of 64 KiB, enough to step back a few frames
+    cpu.rewind = Some(Rewind::new(1000, 64));
+
+    let mut debugger = Debugger {
+        machine: SpaceInvaders::new(cpu),
+        breakpoints: BTreeSet::new(),
+        memory: 0x2000,
+        list: None,
+        input: String::new(),
+        status: "s [n] | r [n] | c | b/d addr | g addr | m addr | l [addr] | p addr value | q"
+            .to_string(),
+        history: VecDeque::new(),
+    };
+
+    let mut terminal = ratatui::init();
+    let result = debugger.event_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
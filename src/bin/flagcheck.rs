@@ -0,0 +1,159 @@
+//! runs every alu opcode on random inputs and checks the result and the
+//! five flags against a reference written from the 8080 manual instead of
+//! the emulator's code. stops at the first divergence with a non-zero exit
+//!
+//!     cargo run --bin flagcheck --features flagcheck -- [iterations] [seed]
+
+use anyhow::{bail, Result};
+
+use intel_8080_emu::cpu::Cpu8080;
+use intel_8080_emu::disassembler::mnemonic;
+
+const DEFAULT_ITERATIONS: u64 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Outcome {
+    result: u8,
+    z: bool,
+    s: bool,
+    p: bool,
+    cy: bool,
+    ac: bool,
+}
+
+impl Outcome {
+    /// z, s and p come from the result alone, the caller passes cy and ac
+    fn new(result: u8, cy: bool, ac: bool) -> Self {
+        Self {
+            result,
+            z: result == 0,
+            s: result & 0x80 != 0,
+            p: result.count_ones().is_multiple_of(2),
+            cy,
+            ac,
+        }
+    }
+}
+
+/// what `op` (0..8, ADD ADC SUB SBB ANA XRA ORA CMP) does to `a` and
+/// `value` with the carry flag at `carry`
+fn reference(op: u8, a: u8, value: u8, carry: bool) -> Outcome {
+    let sum = |value: u8, carry_in: bool| {
+        let wide = a as u16 + value as u16 + carry_in as u16;
+        let ac = (a & 0x0f) + (value & 0x0f) + carry_in as u8 > 0x0f;
+        (wide as u8, wide > 0xff, ac)
+    };
+    // subtraction is an add of the complement, the carry out comes back
+    // inverted as the borrow
+    let difference = |borrow: bool| {
+        let (result, carry_out, ac) = sum(!value, !borrow);
+        (result, !carry_out, ac)
+    };
+    match op {
+        0 | 1 => {
+            let (result, cy, ac) = sum(value, op == 1 && carry);
+            Outcome::new(result, cy, ac)
+        }
+        2 | 3 | 7 => {
+            let (result, cy, ac) = difference(op == 3 && carry);
+            let outcome = Outcome::new(result, cy, ac);
+            // CMP sets the flags from the difference but keeps a
+            match op {
+                7 => Outcome {
+                    result: a,
+                    ..outcome
+                },
+                _ => outcome,
+            }
+        }
+        4 => Outcome::new(a & value, false, (a | value) & 0x08 != 0),
+        5 => Outcome::new(a ^ value, false, false),
+        6 => Outcome::new(a | value, false, false),
+        _ => unreachable!("there are 8 alu operations"),
+    }
+}
+
+/// INR and DCR, which leave cy alone
+fn reference_step(value: u8, carry: bool, increment: bool) -> Outcome {
+    match increment {
+        true => Outcome::new(value.wrapping_add(1), carry, value & 0x0f == 0x0f),
+        false => Outcome::new(value.wrapping_sub(1), carry, value & 0x0f != 0),
+    }
+}
+
+/// `opcode` run once from 0 with `a` and `b` set. the register it writes
+/// is what ends up in `result`
+fn emulate(opcode: u8, a: u8, b: u8, carry: bool) -> Outcome {
+    let mut cpu = Cpu8080::new();
+    cpu.write(0, opcode);
+    cpu.write(1, b);
+    cpu.a = a;
+    cpu.b = b;
+    cpu.cy = carry;
+    cpu.step();
+    let result = match opcode {
+        0x04 | 0x05 => cpu.b,
+        _ => cpu.a,
+    };
+    Outcome {
+        result,
+        z: cpu.z,
+        s: cpu.s,
+        p: cpu.p,
+        cy: cpu.cy,
+        ac: cpu.ac,
+    }
+}
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let iterations = match args.next() {
+        Some(n) => n.parse()?,
+        None => DEFAULT_ITERATIONS,
+    };
+    let mut state: u64 = match args.next() {
+        Some(seed) => seed.parse()?,
+        None => 0x8080,
+    };
+    // splitmix64, same as `Cpu8080::from_seed`
+    let mut next = move || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+
+    for _ in 0..iterations {
+        let [a, value, carry, ..] = next().to_le_bytes();
+        let carry = carry & 1 != 0;
+
+        let mut cases = Vec::new();
+        for op in 0..8 {
+            let expected = reference(op, a, value, carry);
+            // `op B` and the immediate form `opI value`
+            cases.push((0x80 | op << 3, expected));
+            cases.push((0xc6 | op << 3, expected));
+        }
+        cases.push((0x04, reference_step(value, carry, true)));
+        cases.push((0x05, reference_step(value, carry, false)));
+
+        for (opcode, expected) in cases {
+            let actual = emulate(opcode, a, value, carry);
+            if actual != expected {
+                bail!(
+                    "{} ({:#04x}) with a {:#04x}, operand {:#04x}, carry {}:\n  expected {:?}\n  got      {:?}",
+                    mnemonic(opcode),
+                    opcode,
+                    a,
+                    value,
+                    carry as u8,
+                    expected,
+                    actual
+                );
+            }
+        }
+    }
+    println!("{} iterations, no divergence", iterations);
+    Ok(())
+}
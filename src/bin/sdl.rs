@@ -0,0 +1,105 @@
+use anyhow::{anyhow, Context, Result};
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Scancode};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::EventPump;
+
+use intel_8080_emu::cpu::Cpu8080;
+use intel_8080_emu::input::{InputSource, InputState, PlayerInput};
+use intel_8080_emu::invaders::{load_rom, SpaceInvaders, DEFAULT_ROM};
+use intel_8080_emu::video::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+const PIXEL_SIZE: u32 = 3;
+
+struct Keyboard<'a>(&'a EventPump);
+
+impl InputSource for Keyboard<'_> {
+    fn poll(&mut self) -> InputState {
+        let keys = self.0.keyboard_state();
+        let down = |key| keys.is_scancode_pressed(key);
+        InputState {
+            coin: down(Scancode::C),
+            players: [
+                PlayerInput {
+                    start: down(Scancode::Return),
+                    left: down(Scancode::Left),
+                    right: down(Scancode::Right),
+                    fire: down(Scancode::Space),
+                },
+                PlayerInput {
+                    start: down(Scancode::Num2),
+                    left: down(Scancode::A),
+                    right: down(Scancode::D),
+                    fire: down(Scancode::W),
+                },
+            ],
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let path = std::env::args().nth(1).unwrap_or(DEFAULT_ROM.to_string());
+
+    let mut cpu = Cpu8080::new();
+    load_rom(&mut cpu, &path).with_context(|| {
+        format!(
+            "no rom at {}, pass another path as the first argument",
+            path
+        )
+    })?;
+    let mut machine = SpaceInvaders::new(cpu);
+
+    let sdl = sdl2::init().map_err(|err| anyhow!(err))?;
+    let window = sdl
+        .video()
+        .map_err(|err| anyhow!(err))?
+        .window(
+            "8080 Emulator",
+            SCREEN_WIDTH as u32 * PIXEL_SIZE,
+            SCREEN_HEIGHT as u32 * PIXEL_SIZE,
+        )
+        .position_centered()
+        .build()?;
+    let mut canvas = window.into_canvas().present_vsync().build()?;
+    let texture_creator = canvas.texture_creator();
+    let mut screen = texture_creator.create_texture_streaming(
+        PixelFormatEnum::RGBA32,
+        SCREEN_WIDTH as u32,
+        SCREEN_HEIGHT as u32,
+    )?;
+    let mut events = sdl.event_pump().map_err(|err| anyhow!(err))?;
+
+    'running: loop {
+        for event in events.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => break 'running,
+                Event::KeyDown {
+                    keycode: Some(Keycode::P),
+                    repeat: false,
+                    ..
+                } => machine.cpu.set_paused(!machine.cpu.paused),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F),
+                    repeat: false,
+                    ..
+                } => machine.io.cocktail = !machine.io.cocktail,
+                _ => {}
+            }
+        }
+
+        machine.io.update(&mut Keyboard(&events));
+        machine.run_frame();
+
+        screen.update(None, &machine.framebuffer(), SCREEN_WIDTH * 4)?;
+        canvas
+            .copy(&screen, None, None)
+            .map_err(|err| anyhow!(err))?;
+        canvas.present();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use std::ops::{Deref, DerefMut};
+
+/// what the cpu reads and writes memory through. the cpu is generic over it
+/// so the calls inline instead of going through a vtable on every access
+pub trait Bus {
+    fn read(&self, addr: u16) -> u8;
+    fn write(&mut self, addr: u16, value: u8);
+
+    /// a store that gets past any rom, for loading it and for cheats. plain
+    /// ram has none, so it is `write` unless the bus says otherwise
+    fn poke(&mut self, addr: u16, value: u8) {
+        self.write(addr, value);
+    }
+}
+
+/// 64 KiB of plain ram, what the cpu uses unless told otherwise
+#[derive(Debug, Clone)]
+pub struct FlatMemory([u8; 0x10000]);
+
+impl FlatMemory {
+    pub fn new() -> Self {
+        Self([0; 0x10000])
+    }
+}
+
+impl Default for FlatMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bus for FlatMemory {
+    #[inline]
+    fn read(&self, addr: u16) -> u8 {
+        self.0[addr as usize]
+    }
+
+    #[inline]
+    fn write(&mut self, addr: u16, value: u8) {
+        self.0[addr as usize] = value;
+    }
+}
+
+/// lets callers slice it like the array it replaced
+impl Deref for FlatMemory {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl DerefMut for FlatMemory {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
@@ -0,0 +1,30 @@
+use crate::config::MachineConfig;
+
+/// where the front-end gets the length of each frame from
+pub trait ClockSource {
+    /// seconds since the previous frame
+    fn frame_time(&mut self) -> f32;
+}
+
+/// the same `dt` every frame, for runs that must come out the same each time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedStep {
+    pub dt: f32,
+}
+
+impl FixedStep {
+    pub fn new(dt: f32) -> Self {
+        Self { dt }
+    }
+
+    /// one frame of the machine's monitor per step
+    pub fn for_config(config: &MachineConfig) -> Self {
+        Self::new(1. / config.frame_hz as f32)
+    }
+}
+
+impl ClockSource for FixedStep {
+    fn frame_time(&mut self) -> f32 {
+        self.dt
+    }
+}
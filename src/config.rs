@@ -0,0 +1,51 @@
+use std::ops::Range;
+
+/// timing and screen of the machine around the 8080
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MachineConfig {
+    pub clock_hz: u32,
+    pub frame_hz: u32,
+    pub screen_width: usize,
+    pub screen_height: usize,
+    /// line of `screen_height` where the mid-frame interrupt fires, the
+    /// end-of-frame one always fires after the last line
+    pub interrupt_scanline: usize,
+    /// where the one bit per pixel video memory starts and how many bytes
+    /// it takes, stored in columns of the screen turned on its side
+    pub vram_base: u16,
+    pub vram_len: u16,
+}
+
+/// 2 MHz 8080, 60 Hz monitor turned on its side, RST 1 at mid-screen
+pub const SPACE_INVADERS: MachineConfig = MachineConfig {
+    clock_hz: 2_000_000,
+    frame_hz: 60,
+    screen_width: 224,
+    screen_height: 256,
+    interrupt_scanline: 128,
+    vram_base: 0x2400,
+    vram_len: 0x1c00,
+};
+
+impl MachineConfig {
+    /// the vram addresses, as usize so a region ending at 0xffff fits
+    pub const fn vram(&self) -> Range<usize> {
+        self.vram_base as usize..self.vram_base as usize + self.vram_len as usize
+    }
+
+    pub const fn cycles_per_frame(&self) -> u32 {
+        self.clock_hz / self.frame_hz
+    }
+
+    /// cycles into the frame when the beam reaches `interrupt_scanline`
+    pub const fn interrupt_cycle(&self) -> u32 {
+        (self.cycles_per_frame() as u64 * self.interrupt_scanline as u64
+            / self.screen_height as u64) as u32
+    }
+}
+
+impl Default for MachineConfig {
+    fn default() -> Self {
+        SPACE_INVADERS
+    }
+}
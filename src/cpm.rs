@@ -0,0 +1,141 @@
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+
+use anyhow::{bail, Result};
+
+use crate::bus::Bus;
+use crate::cpu::Cpu8080;
+
+/// programs are loaded and started here, below it is the zero page
+pub const TPA: u16 = 0x0100;
+/// programs CALL here with the function number in C
+const BDOS: u16 = 0x0005;
+/// top of the memory a program may use, read from the word after `BDOS`
+const MEMORY_TOP: u16 = 0xfe00;
+/// ^Z, what CP/M reads once there is no more input
+const END_OF_FILE: u8 = 0x1a;
+
+/// the terminal a CP/M program talks to through the BDOS: output goes to
+/// the `Write` side, BDOS 1 takes its keys from `key`
+pub trait Console: Write {
+    fn key(&mut self) -> u8;
+}
+
+/// the real terminal, keys come from stdin
+impl Console for std::io::Stdout {
+    fn key(&mut self) -> u8 {
+        let mut byte = [END_OF_FILE];
+        match std::io::stdin().read(&mut byte) {
+            Ok(1) => byte[0],
+            _ => END_OF_FILE,
+        }
+    }
+}
+
+/// a terminal in memory: keys are queued up front and everything the
+/// program prints is kept in `output`
+#[derive(Debug, Default)]
+pub struct CpmConsole {
+    input: VecDeque<u8>,
+    pub output: Vec<u8>,
+}
+
+impl CpmConsole {
+    /// a console that types `input`, then ^Z from then on
+    pub fn new(input: &[u8]) -> Self {
+        Self {
+            input: input.iter().copied().collect(),
+            output: Vec::new(),
+        }
+    }
+
+    /// `output` as text, bytes that aren't utf-8 are replaced
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.output).into_owned()
+    }
+}
+
+impl Write for CpmConsole {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Console for CpmConsole {
+    fn key(&mut self) -> u8 {
+        self.input.pop_front().unwrap_or(END_OF_FILE)
+    }
+}
+
+/// puts `program` at `TPA` and starts it there. warm boot (a jump to 0)
+/// halts, and the BDOS entry is a RET that `run_cpm` intercepts
+pub fn load_cpm<B: Bus>(cpu: &mut Cpu8080<B>, program: &[u8]) -> Result<()> {
+    cpu.load_at(program, TPA)?;
+    cpu.load_at(&[0x76], 0x0000)?;
+    let [low, high] = MEMORY_TOP.to_le_bytes();
+    cpu.load_at(&[0xc9, low, high], BDOS)?;
+    cpu.pc = TPA;
+    Ok(())
+}
+
+/// runs the loaded program until it warm boots, handling BDOS 1, 2 and 9 on
+/// `console`. returns the instructions executed. enough for the 8080
+/// exercisers: 8080PRE finishes right away, 8080EXM runs for minutes even
+/// in a release build. a paused cpu, or a HLT anywhere else with interrupts
+/// off, would never get there and is an error instead
+pub fn run_cpm<B: Bus>(cpu: &mut Cpu8080<B>, console: &mut dyn Console) -> Result<u64> {
+    let mut steps = 0;
+    while !cpu.halt {
+        if cpu.paused {
+            bail!("cpu is paused at {:#06x}", cpu.pc);
+        }
+        if cpu.pc == BDOS {
+            bdos(cpu, console)?;
+        }
+        cpu.step();
+        // the exercisers run billions of instructions, keeping every line
+        // would run out of memory
+        cpu.history.clear();
+        steps += 1;
+    }
+    console.flush()?;
+    // the warm boot HLT sits at 0, so a halt there leaves pc at 1
+    if cpu.pc != 1 && !cpu.interrupt {
+        bail!(
+            "halted at {:#06x} with interrupts disabled",
+            cpu.pc.wrapping_sub(1)
+        );
+    }
+    Ok(steps)
+}
+
+fn bdos<B: Bus>(cpu: &mut Cpu8080<B>, console: &mut dyn Console) -> Result<()> {
+    match cpu.c {
+        // console input echoes the key and returns it in a and l
+        1 => {
+            let key = console.key();
+            console.write_all(&[key])?;
+            cpu.a = key;
+            cpu.l = key;
+        }
+        2 => console.write_all(&[cpu.e])?,
+        9 => {
+            let start = cpu.de();
+            let mut addr = start;
+            while cpu.peek(addr) != b'$' {
+                console.write_all(&[cpu.peek(addr)])?;
+                addr = addr.wrapping_add(1);
+                if addr == start {
+                    bail!("no '$' ends the string at {:#06x}", start);
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,2353 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::io::Write;
+
+use anyhow::{bail, Context, Result};
+
+use crate::access::AccessMap;
+use crate::bus::{Bus, FlatMemory};
+use crate::io::{Io, IoDirection, IoEvent, NullIo};
+use crate::state::Rewind;
+use crate::trace::TraceLine;
+
+#[derive(Debug)]
+pub struct Cpu8080<B: Bus = FlatMemory> {
+    pub a: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+
+    /// program counter
+    pub pc: u16,
+    /// stack pointer
+    pub sp: u16,
+
+    /// zero
+    pub z: bool,
+    /// sign
+    pub s: bool,
+    /// parity
+    pub p: bool,
+    /// carry
+    pub cy: bool,
+    /// auxiliary carry
+    pub ac: bool,
+
+    pub interrupt: bool,
+    /// EI ran and `interrupt` turns on once the next instruction is done,
+    /// so the RET in an `EI; RET` epilogue leaves before another one comes in
+    pub ei_pending: bool,
+    /// RSTs raised with `request_interrupt` that haven't been taken yet
+    pub pending_interrupts: VecDeque<u8>,
+
+    pub halt: bool,
+    /// frozen by the user or a debugger, stepping and interrupts do nothing
+    pub paused: bool,
+
+    /// whatever decodes the addresses, mirroring and rom included
+    pub memory: B,
+
+    pub history: Vec<String>,
+    /// let `history` grow past the step that wrote it. off, each step starts
+    /// it over, so only the last step's lines are left. trace sinks and
+    /// `on_step` see every line either way
+    pub keep_history: bool,
+    /// sp is expected to stay within `stack_floor..=stack_ceiling`, leaving
+    /// it puts a warning in history. the defaults allow the whole space
+    pub stack_floor: u16,
+    pub stack_ceiling: u16,
+    /// count executed opcodes into `profile`
+    pub profiling: bool,
+    pub profile: [u64; 256],
+    /// mark every byte of each executed instruction in `coverage`
+    pub track_coverage: bool,
+    pub coverage: Box<[bool; 0x10000]>,
+    /// record every bus read and write into the access map
+    pub log_access: bool,
+    /// count bus reads and writes, opcode fetches included. see `mem_reads`
+    pub count_memory: bool,
+    /// a Cell for the same reason as `access`
+    mem_reads: Cell<u64>,
+    mem_writes: u64,
+    /// record the last `IO_LOG_LEN` INs and OUTs, see `io_log`
+    pub log_io: bool,
+    io_log: Vec<IoEvent>,
+    /// clock cycles run since the cpu was made
+    pub cycles: u64,
+    /// steps taken since the cpu was made, interrupts included
+    pub steps: u64,
+    /// snapshots for `step_back`, off while `None`
+    pub rewind: Option<Rewind>,
+    /// behind a RefCell so `read` can stay `&self`
+    access: RefCell<AccessMap>,
+    /// return addresses of the CALLs and RSTs not yet returned from, kept
+    /// beside the real stack for debuggers
+    pub(crate) call_stack: Vec<u16>,
+
+    /// turn undocumented opcodes into errors in `try_step`
+    pub strict: bool,
+    /// print every executed instruction from the front-end loops
+    pub trace: bool,
+    /// append what each instruction changed to its history line
+    pub trace_changes: bool,
+    /// called after every executed instruction, see `set_on_step`
+    pub on_step: Option<StepHook>,
+    /// where each executed instruction is written as a trace line, see
+    /// `set_trace_sink`. nothing is written while `None`
+    pub trace_sink: Option<TraceSink>,
+}
+
+/// what `on_step` gets told about the instruction that just ran
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepInfo {
+    pub pc: u16,
+    pub opcode: u8,
+    pub cycles: u8,
+    /// the psw flags byte after the instruction
+    pub flags: u8,
+}
+
+pub struct StepHook(pub Box<dyn FnMut(&StepInfo)>);
+
+impl std::fmt::Debug for StepHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("StepHook")
+    }
+}
+
+pub struct TraceSink(pub Box<dyn Write>);
+
+impl std::fmt::Debug for TraceSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("TraceSink")
+    }
+}
+
+/// a conditional call or return that goes through. the table below has
+/// them at their not-taken cost, 11 and 5
+const CALL_TAKEN_CYCLES: u8 = 17;
+const RET_TAKEN_CYCLES: u8 = 11;
+
+/// how many port accesses `io_log` keeps
+pub const IO_LOG_LEN: usize = 256;
+
+/// clock cycles per opcode. conditional calls and returns are listed at
+/// their not-taken cost
+const CYCLES: [u8; 256] = [
+    4, 10, 7, 5, 5, 5, 7, 4, 4, 10, 7, 5, 5, 5, 7, 4, // 0x00
+    4, 10, 7, 5, 5, 5, 7, 4, 4, 10, 7, 5, 5, 5, 7, 4, // 0x10
+    4, 10, 16, 5, 5, 5, 7, 4, 4, 10, 16, 5, 5, 5, 7, 4, // 0x20
+    4, 10, 13, 5, 10, 10, 10, 4, 4, 10, 13, 5, 5, 5, 7, 4, // 0x30
+    5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, // 0x40
+    5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, // 0x50
+    5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, // 0x60
+    7, 7, 7, 7, 7, 7, 7, 7, 5, 5, 5, 5, 5, 5, 7, 5, // 0x70
+    4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, // 0x80
+    4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, // 0x90
+    4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, // 0xa0
+    4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, // 0xb0
+    5, 10, 10, 10, 11, 11, 7, 11, 5, 10, 10, 10, 11, 17, 7, 11, // 0xc0
+    5, 10, 10, 10, 11, 11, 7, 11, 5, 10, 10, 10, 11, 17, 7, 11, // 0xd0
+    5, 10, 10, 18, 11, 11, 7, 11, 5, 5, 10, 4, 11, 17, 7, 11, // 0xe0
+    5, 10, 10, 4, 11, 11, 7, 11, 5, 5, 10, 4, 11, 17, 7, 11, // 0xf0
+];
+
+/// bytes per opcode including operands, the undocumented ones take as many
+/// as the instruction they alias
+const LENGTHS: [u8; 256] = [
+    1, 3, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // 0x00
+    1, 3, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // 0x10
+    1, 3, 3, 1, 1, 1, 2, 1, 1, 1, 3, 1, 1, 1, 2, 1, // 0x20
+    1, 3, 3, 1, 1, 1, 2, 1, 1, 1, 3, 1, 1, 1, 2, 1, // 0x30
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0x40
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0x50
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0x60
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0x70
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0x80
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0x90
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0xa0
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 0xb0
+    1, 1, 3, 3, 3, 1, 2, 1, 1, 1, 3, 3, 3, 3, 2, 1, // 0xc0
+    1, 1, 3, 2, 3, 1, 2, 1, 1, 1, 3, 2, 3, 3, 2, 1, // 0xd0
+    1, 1, 3, 1, 3, 1, 2, 1, 1, 1, 3, 1, 3, 3, 2, 1, // 0xe0
+    1, 1, 3, 1, 3, 1, 2, 1, 1, 1, 3, 1, 3, 3, 2, 1, // 0xf0
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OpcodeInfo {
+    /// bytes including operands
+    pub length: u8,
+    /// clock cycles, see `CYCLES` for the conditional ones
+    pub cycles: u8,
+}
+
+const fn opcode_table() -> [OpcodeInfo; 256] {
+    let mut table = [OpcodeInfo {
+        length: 1,
+        cycles: 0,
+    }; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = OpcodeInfo {
+            length: LENGTHS[i],
+            cycles: CYCLES[i],
+        };
+        i += 1;
+    }
+    table
+}
+
+/// length and timing of every opcode, what `step` uses to advance pc
+pub const OPCODES: [OpcodeInfo; 256] = opcode_table();
+
+/// bits of the psw byte backed by a flag: s z ac p cy
+const FLAG_MASK: u8 = 0xd5;
+/// bit 1 of the psw byte always reads 1, bits 3 and 5 always 0
+const FLAG_ALWAYS_SET: u8 = 0x02;
+
+/// opcodes the 8080 manual leaves undocumented
+pub const ILLEGAL_OPCODES: [u8; 12] = [
+    0x08, 0x10, 0x18, 0x20, 0x28, 0x30, 0x38, 0xcb, 0xd9, 0xdd, 0xed, 0xfd,
+];
+
+#[derive(Debug, PartialEq)]
+pub enum CpuError {
+    IllegalOpcode {
+        pc: u16,
+        opcode: u8,
+    },
+    /// asked for the instruction at `pc` of a `len` byte image
+    OutOfRange {
+        pc: usize,
+        len: usize,
+    },
+}
+
+impl std::fmt::Display for CpuError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CpuError::IllegalOpcode { pc, opcode } => {
+                write!(f, "illegal opcode {:#04x} at {:#06x}", opcode, pc)
+            }
+            CpuError::OutOfRange { pc, len } => {
+                write!(f, "{:#06x} is past the end of {} bytes", pc, len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CpuError {}
+
+/// what memory holds before anything writes it. real ram powers up with
+/// junk, filling it shows up code that counts on zeros
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemFill {
+    Zero,
+    Byte(u8),
+    /// the same bytes `from_seed` would give for this seed
+    Seeded(u64),
+}
+
+/// splitmix64, small and stable across versions unlike a crate's rng
+fn splitmix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// 8 bit registers by name, `M` is the byte at hl
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reg8 {
+    A,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    M,
+}
+
+/// register pairs and the 16 bit registers by name
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reg16 {
+    BC,
+    DE,
+    HL,
+    SP,
+    PC,
+}
+
+impl Default for Cpu8080 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Cpu8080Builder {
+    trace: bool,
+    strict: bool,
+    rom: Vec<u8>,
+    pc: u16,
+    stack: Option<(u16, u16)>,
+}
+
+impl Cpu8080Builder {
+    pub fn trace(mut self, trace: bool) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// loaded at address 0 on `build`
+    pub fn rom(mut self, rom: &[u8]) -> Self {
+        self.rom = rom.to_vec();
+        self
+    }
+
+    pub fn pc(mut self, pc: u16) -> Self {
+        self.pc = pc;
+        self
+    }
+
+    /// lowest and highest sp the program is allowed to reach
+    pub fn stack(mut self, floor: u16, ceiling: u16) -> Self {
+        self.stack = Some((floor, ceiling));
+        self
+    }
+
+    pub fn build(self) -> Result<Cpu8080> {
+        let mut cpu = Cpu8080::new();
+        cpu.load(&self.rom)?;
+        cpu.pc = self.pc;
+        cpu.trace = self.trace;
+        cpu.strict = self.strict;
+        if let Some((floor, ceiling)) = self.stack {
+            cpu.stack_floor = floor;
+            cpu.stack_ceiling = ceiling;
+        }
+        Ok(cpu)
+    }
+}
+
+/// bit 7, what the s flag copies
+fn sign(b: u8) -> bool {
+    b & 0x80 != 0
+}
+
+/// even parity, what the p flag holds
+fn parity(b: u8) -> bool {
+    b.count_ones().is_multiple_of(2)
+}
+
+macro_rules! flag {
+    ($self:ident, $reg:expr) => {
+        $self.z = $reg == 0;
+        $self.s = sign($reg);
+        $self.p = parity($reg);
+    };
+}
+
+impl Cpu8080 {
+    pub fn new() -> Self {
+        Self::with_bus(FlatMemory::new())
+    }
+
+    pub fn builder() -> Cpu8080Builder {
+        Cpu8080Builder::default()
+    }
+
+    /// memory and registers filled from `seed`, so a fuzzer can hand out a
+    /// seed instead of a 64 KiB dump to reproduce a run. pc starts at 0
+    pub fn from_seed(seed: u64) -> Self {
+        let mut state = seed;
+        let mut cpu = Self::new();
+        for chunk in cpu.memory.chunks_mut(8) {
+            chunk.copy_from_slice(&splitmix(&mut state).to_le_bytes());
+        }
+        let [a, b, c, d, e, h, l, flags] = splitmix(&mut state).to_le_bytes();
+        (cpu.a, cpu.b, cpu.c, cpu.d, cpu.e, cpu.h, cpu.l) = (a, b, c, d, e, h, l);
+        cpu.set_flags_byte(flags);
+        cpu.sp = splitmix(&mut state) as u16;
+        cpu
+    }
+
+    /// `new` with memory set up by `fill` instead of zeroed. registers
+    /// start out the same
+    pub fn new_with_fill(fill: MemFill) -> Self {
+        let mut cpu = Self::new();
+        match fill {
+            MemFill::Zero => {}
+            MemFill::Byte(value) => cpu.memory.fill(value),
+            MemFill::Seeded(seed) => {
+                let mut state = seed;
+                for chunk in cpu.memory.chunks_mut(8) {
+                    chunk.copy_from_slice(&splitmix(&mut state).to_le_bytes());
+                }
+            }
+        }
+        cpu
+    }
+}
+
+impl<B: Bus> Cpu8080<B> {
+    pub fn with_bus(memory: B) -> Self {
+        Self {
+            a: 0,
+            b: 0,
+            c: 0,
+            d: 0,
+            e: 0,
+            h: 0,
+            l: 0,
+            pc: 0,
+            sp: 0,
+            z: false,
+            s: false,
+            p: false,
+            cy: false,
+            ac: false,
+            interrupt: false,
+            ei_pending: false,
+            pending_interrupts: VecDeque::new(),
+            halt: false,
+            paused: false,
+            memory,
+            history: Vec::new(),
+            keep_history: true,
+            stack_floor: 0,
+            stack_ceiling: 0xffff,
+            profiling: false,
+            profile: [0; 256],
+            track_coverage: false,
+            coverage: Box::new([false; 0x10000]),
+            log_access: false,
+            count_memory: false,
+            mem_reads: Cell::new(0),
+            mem_writes: 0,
+            access: RefCell::new(AccessMap::new()),
+            log_io: false,
+            io_log: Vec::new(),
+            cycles: 0,
+            steps: 0,
+            rewind: None,
+            call_stack: Vec::new(),
+            strict: false,
+            trace: false,
+            trace_changes: false,
+            on_step: None,
+            trace_sink: None,
+        }
+    }
+
+    /// the same cpu on another bus made from its memory, for putting a cpu
+    /// with the rom loaded onto a board that decodes addresses its own way
+    pub fn map_bus<C: Bus>(self, bus: impl FnOnce(B) -> C) -> Cpu8080<C> {
+        let Cpu8080 {
+            a,
+            b,
+            c,
+            d,
+            e,
+            h,
+            l,
+            pc,
+            sp,
+            z,
+            s,
+            p,
+            cy,
+            ac,
+            interrupt,
+            ei_pending,
+            pending_interrupts,
+            halt,
+            paused,
+            memory,
+            history,
+            keep_history,
+            stack_floor,
+            stack_ceiling,
+            profiling,
+            profile,
+            track_coverage,
+            coverage,
+            log_access,
+            count_memory,
+            mem_reads,
+            mem_writes,
+            access,
+            log_io,
+            io_log,
+            cycles,
+            steps,
+            rewind,
+            call_stack,
+            strict,
+            trace,
+            trace_changes,
+            on_step,
+            trace_sink,
+        } = self;
+        Cpu8080 {
+            a,
+            b,
+            c,
+            d,
+            e,
+            h,
+            l,
+            pc,
+            sp,
+            z,
+            s,
+            p,
+            cy,
+            ac,
+            interrupt,
+            ei_pending,
+            pending_interrupts,
+            halt,
+            paused,
+            memory: bus(memory),
+            history,
+            keep_history,
+            stack_floor,
+            stack_ceiling,
+            profiling,
+            profile,
+            track_coverage,
+            coverage,
+            log_access,
+            count_memory,
+            mem_reads,
+            mem_writes,
+            access,
+            log_io,
+            io_log,
+            cycles,
+            steps,
+            rewind,
+            call_stack,
+            strict,
+            trace,
+            trace_changes,
+            on_step,
+            trace_sink,
+        }
+    }
+
+    pub fn bc(&self) -> u16 {
+        (self.b as u16) << 8 | self.c as u16
+    }
+
+    pub fn de(&self) -> u16 {
+        (self.d as u16) << 8 | self.e as u16
+    }
+
+    pub fn hl(&self) -> u16 {
+        (self.h as u16) << 8 | self.l as u16
+    }
+
+    pub fn set_bc(&mut self, value: u16) {
+        self.b = (value >> 8) as u8;
+        self.c = value as u8;
+    }
+
+    pub fn set_de(&mut self, value: u16) {
+        self.d = (value >> 8) as u8;
+        self.e = value as u8;
+    }
+
+    pub fn set_hl(&mut self, value: u16) {
+        self.h = (value >> 8) as u8;
+        self.l = value as u8;
+    }
+
+    pub fn get_reg(&self, reg: Reg8) -> u8 {
+        match reg {
+            Reg8::A => self.a,
+            Reg8::B => self.b,
+            Reg8::C => self.c,
+            Reg8::D => self.d,
+            Reg8::E => self.e,
+            Reg8::H => self.h,
+            Reg8::L => self.l,
+            Reg8::M => self.read(self.hl()),
+        }
+    }
+
+    pub fn set_reg(&mut self, reg: Reg8, value: u8) {
+        match reg {
+            Reg8::A => self.a = value,
+            Reg8::B => self.b = value,
+            Reg8::C => self.c = value,
+            Reg8::D => self.d = value,
+            Reg8::E => self.e = value,
+            Reg8::H => self.h = value,
+            Reg8::L => self.l = value,
+            Reg8::M => self.write(self.hl(), value),
+        }
+    }
+
+    pub fn get_reg16(&self, reg: Reg16) -> u16 {
+        match reg {
+            Reg16::BC => self.bc(),
+            Reg16::DE => self.de(),
+            Reg16::HL => self.hl(),
+            Reg16::SP => self.sp,
+            Reg16::PC => self.pc,
+        }
+    }
+
+    pub fn set_reg16(&mut self, reg: Reg16, value: u16) {
+        match reg {
+            Reg16::BC => self.set_bc(value),
+            Reg16::DE => self.set_de(value),
+            Reg16::HL => self.set_hl(value),
+            Reg16::SP => self.sp = value,
+            Reg16::PC => self.pc = value,
+        }
+    }
+
+    pub fn load(&mut self, rom: &[u8]) -> Result<()> {
+        self.load_at(rom, 0)
+    }
+
+    pub fn load_at(&mut self, rom: &[u8], offset: u16) -> Result<()> {
+        let start = offset as usize;
+        let end = start + rom.len();
+        if end > 0x10000 {
+            bail!(
+                "rom of {} bytes at {:#06x} does not fit in memory",
+                rom.len(),
+                offset
+            );
+        }
+        // an open `offset..` would overflow after yielding 0xffff
+        for (i, &byte) in rom.iter().enumerate() {
+            self.memory.poke(offset.wrapping_add(i as u16), byte);
+        }
+        Ok(())
+    }
+
+    /// reads `path` and loads it at 0, returning its length
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_file(&mut self, path: &str) -> Result<usize> {
+        let rom = std::fs::read(path).with_context(|| format!("unable to read {}", path))?;
+        self.load(&rom)?;
+        Ok(rom.len())
+    }
+
+    /// there is no filesystem in the browser, the wasm build only has `load`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_files(&mut self, files: &[(&str, u16)]) -> Result<()> {
+        for (path, offset) in files {
+            let rom = std::fs::read(path).with_context(|| format!("unable to read {}", path))?;
+            self.load_at(&rom, *offset)?;
+        }
+        Ok(())
+    }
+
+    /// every store goes through here, STAX and the stack included, so the
+    /// bus's mirroring and rom apply the same way to all of them
+    pub fn write(&mut self, addr: u16, value: u8) {
+        if self.log_access {
+            self.access.get_mut().mark_written(addr);
+        }
+        if self.count_memory {
+            self.mem_writes += 1;
+        }
+        self.memory.write(addr, value);
+    }
+
+    /// `write` that gets past the rom and stays out of the access map, for
+    /// cheats and setting up tests. mirroring still applies
+    pub fn poke(&mut self, addr: u16, value: u8) {
+        self.memory.poke(addr, value);
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        if self.log_access {
+            self.access.borrow_mut().mark_read(addr);
+        }
+        if self.count_memory {
+            self.mem_reads.set(self.mem_reads.get() + 1);
+        }
+        self.peek(addr)
+    }
+
+    /// `read` without it showing up in the access map
+    pub fn peek(&self, addr: u16) -> u8 {
+        self.memory.read(addr)
+    }
+
+    /// bus reads counted while `count_memory` was on
+    pub fn mem_reads(&self) -> u64 {
+        self.mem_reads.get()
+    }
+
+    /// bus writes counted while `count_memory` was on
+    pub fn mem_writes(&self) -> u64 {
+        self.mem_writes
+    }
+
+    pub fn reset_memory_counts(&mut self) {
+        self.mem_reads.set(0);
+        self.mem_writes = 0;
+    }
+
+    /// what the program has read and written so far
+    pub fn access_map(&self) -> AccessMap {
+        self.access.borrow().clone()
+    }
+
+    /// 16 bytes per line, prefixed with the address of the first byte and
+    /// followed by an ascii gutter like `hexdump -C`
+    pub fn hexdump(&self, start: u16, len: usize) -> String {
+        let mut out = String::new();
+        for line in (0..len).step_by(0x10) {
+            let addr = start.wrapping_add(line as u16);
+            let count = (len - line).min(0x10);
+            let mut ascii = String::new();
+
+            out.push_str(&format!("{:#06x} ", addr));
+            for i in 0..count {
+                let byte = self.peek(addr.wrapping_add(i as u16));
+                out.push_str(&format!(" {:#04x}", byte));
+                ascii.push(match byte.is_ascii_graphic() || byte == b' ' {
+                    true => byte as char,
+                    false => '.',
+                });
+            }
+            // keep the gutter aligned on a short last line
+            out.push_str(&" ".repeat((0x10 - count) * 5));
+            out.push_str(&format!("  |{}|\n", ascii));
+        }
+        out
+    }
+
+    /// `depth` words from sp upward with their addresses, top of the stack
+    /// first. wraps past 0xffff like the cpu does
+    pub fn stack_view(&self, depth: usize) -> Vec<(u16, u16)> {
+        (0..depth)
+            .map(|i| {
+                let addr = self.sp.wrapping_add(2 * i as u16);
+                let low = self.peek(addr) as u16;
+                (addr, low | (self.peek(addr.wrapping_add(1)) as u16) << 8)
+            })
+            .collect()
+    }
+
+    /// little endian, the low byte lives at `addr`
+    pub fn read_word(&self, addr: u16) -> u16 {
+        self.read(addr) as u16 | (self.read(addr.wrapping_add(1)) as u16) << 8
+    }
+
+    pub fn write_word(&mut self, addr: u16, value: u16) {
+        self.write(addr, value as u8);
+        self.write(addr.wrapping_add(1), (value >> 8) as u8);
+    }
+
+    /// sets `len` bytes from `start` to `value`, wrapping past 0xffff
+    pub fn fill(&mut self, start: u16, len: usize, value: u8) {
+        for i in 0..len {
+            self.write(start.wrapping_add(i as u16), value);
+        }
+    }
+
+    /// whether memory from `start` holds `data`, wrapping past 0xffff
+    pub fn compare(&self, start: u16, data: &[u8]) -> bool {
+        data.iter()
+            .enumerate()
+            .all(|(i, &byte)| self.peek(start.wrapping_add(i as u16)) == byte)
+    }
+
+    /// computes `a + value + carry` and sets every flag from it, ac being
+    /// the carry out of bit 3
+    fn add(&mut self, value: u8, carry: bool) -> u8 {
+        let result = self.a as u16 + value as u16 + carry as u16;
+        let a = result as u8;
+        flag!(self, a);
+        self.cy = result > 0xff;
+        self.ac = (self.a & 0x0f) + (value & 0x0f) + carry as u8 > 0x0f;
+        a
+    }
+
+    /// INR leaves cy alone, ac is set when the low nibble wraps to 0
+    fn inr(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_add(1);
+        flag!(self, result);
+        self.ac = result & 0x0f == 0;
+        result
+    }
+
+    /// DCR adds 0xff like the rest of the subtracts, so ac is set unless the
+    /// low nibble borrowed
+    fn dcr(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_sub(1);
+        flag!(self, result);
+        self.ac = result & 0x0f != 0x0f;
+        result
+    }
+
+    /// computes `a - value - borrow` and sets every flag from it without
+    /// touching `a`. the 8080 subtracts by adding the complement, so ac is
+    /// the carry out of bit 3 of that add: set when there is *no* half borrow
+    fn sub(&mut self, value: u8, borrow: bool) -> u8 {
+        let result = (self.a as u16)
+            .wrapping_sub(value as u16)
+            .wrapping_sub(borrow as u16);
+        let a = result as u8;
+        flag!(self, a);
+        self.cy = result > 0xff;
+        // a + !value + !borrow, low nibbles only. not the half borrow
+        // `(a & 0xf) - (value & 0xf) - borrow < 0`: the alu has no
+        // subtractor, SUB and SBB feed the complement and a carry in to the
+        // adder and ac is whatever it carries out of bit 3. the inverse of
+        // the half borrow, and what real chips and 8080EXM report
+        self.ac = (self.a & 0x0f) + (!value & 0x0f) + !borrow as u8 > 0x0f;
+        a
+    }
+
+    /// the 8080 keeps the low byte at sp and the high byte at sp + 1
+    fn pop(&mut self) -> u16 {
+        let value = self.read_word(self.sp);
+        self.sp = self.sp.wrapping_add(2);
+        self.check_stack();
+        value
+    }
+
+    fn push(&mut self, value: u16) {
+        self.sp = self.sp.wrapping_sub(2);
+        self.check_stack();
+        self.write_word(self.sp, value);
+    }
+
+    fn check_stack(&mut self) {
+        if self.sp < self.stack_floor {
+            self.history
+                .push(format!("warning: stack overflow, sp {:#06x}", self.sp));
+        } else if self.sp > self.stack_ceiling {
+            self.history
+                .push(format!("warning: stack underflow, sp {:#06x}", self.sp));
+        }
+    }
+
+    /// pc already points past the instruction, that is the return address
+    fn call(&mut self, addr: u16) {
+        let ret = self.pc;
+        self.push(ret);
+        self.call_stack.push(ret);
+        self.pc = addr;
+    }
+
+    /// RST n is a one byte call to `n * 8`. `interrupt` and
+    /// `request_interrupt` check `n` where it comes in
+    fn rst(&mut self, n: u8) {
+        self.call(n as u16 * 8);
+    }
+
+    /// pops the return address, warning in history when the program has
+    /// unbalanced the stack behind the shadow one's back
+    fn ret(&mut self) {
+        let addr = self.pop();
+        match self.call_stack.pop() {
+            None => self
+                .history
+                .push(format!("warning: RET to {:#06x} with no call", addr)),
+            Some(expected) if expected != addr => self.history.push(format!(
+                "warning: RET to {:#06x}, call expected {:#06x}",
+                addr, expected
+            )),
+            _ => {}
+        }
+        self.pc = addr;
+    }
+
+    /// executed opcodes, most frequent first, leaving out the ones never run
+    pub fn profile_report(&self) -> Vec<(u8, u64)> {
+        let mut report: Vec<(u8, u64)> = (0..=255)
+            .map(|opcode| (opcode, self.profile[opcode as usize]))
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        report.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        report
+    }
+
+    /// the executed parts of memory as inclusive `(first, last)` ranges
+    pub fn coverage_report(&self) -> Vec<(u16, u16)> {
+        let mut ranges = Vec::new();
+        let mut start = None;
+        for (addr, &covered) in self.coverage.iter().enumerate() {
+            match (covered, start) {
+                (true, None) => start = Some(addr as u16),
+                (false, Some(first)) => {
+                    ranges.push((first, addr as u16 - 1));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(first) = start {
+            ranges.push((first, 0xffff));
+        }
+        ranges
+    }
+
+    /// the most recent INs and OUTs, oldest first
+    pub fn io_log(&self) -> &[IoEvent] {
+        &self.io_log[self.io_log.len().saturating_sub(IO_LOG_LEN)..]
+    }
+
+    /// the log runs up to twice its length before the old half is dropped,
+    /// so it stays one slice without shifting on every access
+    fn log_port(&mut self, direction: IoDirection, port: u8, value: u8) {
+        if !self.log_io {
+            return;
+        }
+        if self.io_log.len() == 2 * IO_LOG_LEN {
+            self.io_log.drain(..IO_LOG_LEN);
+        }
+        self.io_log.push(IoEvent {
+            cycle: self.cycles,
+            direction,
+            port,
+            value,
+        });
+    }
+
+    pub fn call_stack(&self) -> &[u16] {
+        &self.call_stack
+    }
+
+    /// the flags as PUSH PSW stores them: s z 0 ac 0 p 1 cy, from bit 7 down
+    pub fn flags_byte(&self) -> u8 {
+        (self.s as u8) << 7
+            | (self.z as u8) << 6
+            | (self.ac as u8) << 4
+            | (self.p as u8) << 2
+            | FLAG_ALWAYS_SET
+            | self.cy as u8
+    }
+
+    /// the fixed bits of `value` are ignored, `flags_byte` puts them back
+    pub fn set_flags_byte(&mut self, value: u8) {
+        let value = value & FLAG_MASK;
+        self.s = sign(value);
+        self.z = value & (1 << 6) != 0;
+        self.ac = value & (1 << 4) != 0;
+        self.p = value & (1 << 2) != 0;
+        self.cy = value & 1 != 0;
+    }
+
+    /// what the interrupt controller does between two instructions: jam an
+    /// RST onto the bus. ignored while interrupts are disabled, and taking
+    /// one disables them until the handler runs EI
+    pub fn interrupt(&mut self, rst: u8) {
+        assert!(rst < 8, "RST {} does not exist", rst);
+        if !self.interrupt || self.paused {
+            return;
+        }
+        self.interrupt = false;
+        self.halt = false;
+        self.rst(rst);
+    }
+
+    /// raises RST `rst` like a latched interrupt line: it waits until
+    /// interrupts are enabled and is taken at the start of a step. raising
+    /// one that is already waiting does nothing. panics here rather than
+    /// when it is taken if there is no RST `rst`
+    pub fn request_interrupt(&mut self, rst: u8) {
+        assert!(rst < 8, "RST {} does not exist", rst);
+        self.rewind_request(rst);
+        if !self.pending_interrupts.contains(&rst) {
+            self.pending_interrupts.push_back(rst);
+        }
+    }
+
+    /// write a `0x1234 MNEMONIC` line for every instruction to `sink` from
+    /// now on, `history` is kept either way
+    pub fn set_trace_sink(&mut self, sink: impl Write + 'static) {
+        self.trace_sink = Some(TraceSink(Box::new(sink)));
+    }
+
+    /// run `hook` after every instruction from now on
+    pub fn set_on_step(&mut self, hook: impl FnMut(&StepInfo) + 'static) {
+        self.on_step = Some(StepHook(Box::new(hook)));
+    }
+
+    /// stopped on HLT. only an interrupt gets it going again, so with
+    /// interrupts disabled it stays stuck
+    pub fn is_halted(&self) -> bool {
+        self.halt
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// like `step` but in strict mode an undocumented opcode is an error and
+    /// leaves the cpu untouched. returns the opcode that was executed
+    pub fn try_step(&mut self) -> Result<u8, CpuError> {
+        let opcode = self.read(self.pc);
+        if self.strict && ILLEGAL_OPCODES.contains(&opcode) {
+            return Err(CpuError::IllegalOpcode {
+                pc: self.pc,
+                opcode,
+            });
+        }
+        self.step();
+        Ok(opcode)
+    }
+
+    /// runs up to `n` instructions, stopping early on HLT or pause. returns the pc
+    /// before the first and after the last executed instruction
+    pub fn step_n(&mut self, n: usize) -> (u16, u16) {
+        let start = self.pc;
+        for _ in 0..n {
+            if self.halt || self.paused {
+                break;
+            }
+            self.step();
+        }
+        (start, self.pc)
+    }
+
+    /// steps until pc reaches `target` or `max_steps` run out, with the trace
+    /// sink and change tracking off and history kept from growing. returns
+    /// whether it got there
+    pub fn seek_to_pc(&mut self, target: u16, max_steps: usize) -> bool {
+        let sink = self.trace_sink.take();
+        let changes = std::mem::replace(&mut self.trace_changes, false);
+        let history = self.history.len();
+        let mut steps = 0;
+        while self.pc != target && steps < max_steps && !self.paused {
+            self.step();
+            self.history.truncate(history);
+            steps += 1;
+        }
+        self.trace_sink = sink;
+        self.trace_changes = changes;
+        self.pc == target
+    }
+
+    /// runs one instruction with nothing attached to the io ports
+    pub fn step(&mut self) -> u8 {
+        self.step_io(&mut NullIo)
+    }
+
+    /// runs one instruction, IN and OUT go to `io`. returns the clock cycles
+    /// it took, 0 while paused. a halted cpu only waits for an interrupt
+    pub fn step_io(&mut self, io: &mut dyn Io) -> u8 {
+        if self.paused {
+            return 0;
+        }
+        if !self.keep_history {
+            self.history.clear();
+        }
+        self.rewind_snapshot();
+        self.steps += 1;
+        // taking an interrupt is a step of its own, the RST it jams in
+        if self.interrupt {
+            if let Some(rst) = self.pending_interrupts.pop_front() {
+                self.interrupt(rst);
+                let cycles = OPCODES[0xc7].cycles;
+                self.cycles += cycles as u64;
+                return cycles;
+            }
+        }
+        // idling a NOP's worth per step keeps the clock, and whatever raises
+        // interrupts from it, running
+        if self.halt {
+            let cycles = OPCODES[0x00].cycles;
+            self.cycles += cycles as u64;
+            return cycles;
+        }
+        let pc = self.pc;
+        let opcode = self.read(pc);
+        let info = OPCODES[opcode as usize];
+        if self.profiling {
+            self.profile[opcode as usize] += 1;
+        }
+        if self.track_coverage {
+            for i in 0..info.length {
+                self.coverage[pc.wrapping_add(i as u16) as usize] = true;
+            }
+        }
+        // word operands (LXI, LDA, STA, LHLD, SHLD and the jumps) are stored
+        // low byte first, `read_word` puts them back together
+        let operand = match info.length {
+            2 => self.read(pc.wrapping_add(1)) as u16,
+            3 => self.read_word(pc.wrapping_add(1)),
+            _ => 0,
+        };
+        self.pc = pc.wrapping_add(info.length as u16);
+
+        let sp = self.sp;
+        let before = self.trace_changes.then(|| TraceLine::capture(self));
+        let first = self.history.len();
+        let enabling = self.ei_pending;
+        self.execute(opcode, operand, io);
+        // a DI in between cancels it, EI's own step only arms it
+        if enabling && self.ei_pending {
+            self.ei_pending = false;
+            self.interrupt = true;
+        }
+        // every arm pushes its own line last, after any warnings it raised
+        if let Some(before) = before {
+            let changes = before.changes(&TraceLine::capture(self));
+            if let (false, Some(line)) = (changes.is_empty(), self.history.last_mut()) {
+                line.push_str(" ; ");
+                line.push_str(&changes);
+            }
+        }
+        if let Some(sink) = &mut self.trace_sink {
+            for text in self.history.get(first..).unwrap_or_default() {
+                // a sink that stops taking lines shouldn't stop the program
+                let _ = writeln!(sink.0, "{:#06x} {}", pc, text);
+            }
+        }
+
+        // Ccc and Rcc are the only opcodes whose cost depends on the outcome,
+        // and a taken one is the only way they touch sp
+        let cycles = match (opcode & 0xc7, self.sp != sp) {
+            (0xc4, true) => CALL_TAKEN_CYCLES,
+            (0xc0, true) => RET_TAKEN_CYCLES,
+            _ => info.cycles,
+        };
+        self.cycles += cycles as u64;
+        if self.on_step.is_some() {
+            let step = StepInfo {
+                pc,
+                opcode,
+                cycles,
+                flags: self.flags_byte(),
+            };
+            if let Some(hook) = &mut self.on_step {
+                (hook.0)(&step);
+            }
+        }
+        cycles
+    }
+
+    /// `step_io` has already moved pc past the instruction and fetched its
+    /// `operand`, so arms only touch pc to jump, and a jump sets it exactly
+    fn execute(&mut self, opcode: u8, operand: u16, io: &mut dyn Io) {
+        match opcode {
+            // the undocumented opcodes decode as their documented siblings on
+            // real silicon, so they are executed as such here. strict mode in
+            // `try_step` is there for callers that want to reject them instead
+            0x00 | 0x08 | 0x10 | 0x18 | 0x20 | 0x28 | 0x30 | 0x38 => {
+                self.history.push("NOP".to_string())
+            }
+            0x01 => {
+                let addr = operand;
+                self.set_bc(addr);
+                self.history.push(format!("LXI B, {:#06x}", addr));
+            }
+            0x02 => {
+                self.write(self.bc(), self.a);
+                self.history.push("STAX B".to_string());
+            }
+            0x03 => {
+                self.set_bc(self.bc().wrapping_add(1));
+                self.history.push("INX B".to_string());
+            }
+            0x04 => {
+                self.b = self.inr(self.b);
+                self.history.push("INR B".to_string());
+            }
+            0x05 => {
+                self.b = self.dcr(self.b);
+                self.history.push("DCR B".to_string());
+            }
+            0x06 => {
+                self.b = operand as u8;
+                self.history.push(format!("MVI B, {:#04x}", self.b));
+            }
+            0x07 => {
+                self.cy = sign(self.a);
+                self.a = self.a.rotate_left(1);
+                self.history.push("RLC".to_string());
+            }
+            0x09 => {
+                let (hl, overflow) = self.hl().overflowing_add(self.bc());
+                self.set_hl(hl);
+                self.cy = overflow;
+                self.history.push("DAD B".to_string());
+            }
+            0x0a => {
+                self.a = self.read(self.bc());
+                self.history.push("LDAX B".to_string());
+            }
+            0x0b => {
+                self.set_bc(self.bc().wrapping_sub(1));
+                self.history.push("DCX B".to_string());
+            }
+            0x0c => {
+                self.c = self.inr(self.c);
+                self.history.push("INR C".to_string());
+            }
+            0x0d => {
+                self.c = self.dcr(self.c);
+                self.history.push("DCR C".to_string());
+            }
+            0x0e => {
+                self.c = operand as u8;
+                self.history.push(format!("MVI C, {:#04x}", self.c));
+            }
+            0x0f => {
+                self.cy = self.a & 1 != 0;
+                self.a = self.a.rotate_right(1);
+                self.history.push("RRC".to_string());
+            }
+            0x11 => {
+                let addr = operand;
+                self.set_de(addr);
+                self.history.push(format!("LXI D, {:#06x}", addr));
+            }
+            0x12 => {
+                self.write(self.de(), self.a);
+                self.history.push("STAX D".to_string());
+            }
+            0x13 => {
+                self.set_de(self.de().wrapping_add(1));
+                self.history.push("INX D".to_string());
+            }
+            0x14 => {
+                self.d = self.inr(self.d);
+                self.history.push("INR D".to_string());
+            }
+            0x15 => {
+                self.d = self.dcr(self.d);
+                self.history.push("DCR D".to_string());
+            }
+            0x16 => {
+                self.d = operand as u8;
+                self.history.push(format!("MVI D, {:#04x}", self.d));
+            }
+            0x17 => {
+                // rotates only ever touch the carry, z/s/p/ac are left alone
+                let cy = sign(self.a);
+                self.a = self.a << 1 | self.cy as u8;
+                self.cy = cy;
+                self.history.push("RAL".to_string());
+            }
+            0x19 => {
+                let (hl, overflow) = self.hl().overflowing_add(self.de());
+                self.set_hl(hl);
+                self.cy = overflow;
+                self.history.push("DAD D".to_string());
+            }
+            0x1a => {
+                self.a = self.read(self.de());
+                self.history.push("LDAX D".to_string());
+            }
+            0x1b => {
+                self.set_de(self.de().wrapping_sub(1));
+                self.history.push("DCX D".to_string());
+            }
+            0x1c => {
+                self.e = self.inr(self.e);
+                self.history.push("INR E".to_string());
+            }
+            0x1d => {
+                self.e = self.dcr(self.e);
+                self.history.push("DCR E".to_string());
+            }
+            0x1e => {
+                self.e = operand as u8;
+                self.history.push(format!("MVI E, {:#04x}", self.e));
+            }
+            0x1f => {
+                let cy = self.a & 1 != 0;
+                self.a = self.a >> 1 | (self.cy as u8) << 7;
+                self.cy = cy;
+                self.history.push("RAR".to_string());
+            }
+            0x21 => {
+                let addr = operand;
+                self.set_hl(addr);
+                self.history.push(format!("LXI H, {:#06x}", addr));
+            }
+            0x22 => {
+                let addr = operand;
+                self.write_word(addr, self.hl());
+                self.history.push(format!("SHLD {:#06x}", addr));
+            }
+            0x23 => {
+                self.set_hl(self.hl().wrapping_add(1));
+                self.history.push("INX H".to_string());
+            }
+            0x24 => {
+                self.h = self.inr(self.h);
+                self.history.push("INR H".to_string());
+            }
+            0x25 => {
+                self.h = self.dcr(self.h);
+                self.history.push("DCR H".to_string());
+            }
+            0x26 => {
+                self.h = operand as u8;
+                self.history.push(format!("MVI H, {:#04x}", self.h));
+            }
+            0x27 => {
+                let mut correction = 0;
+                if self.a & 0x0f > 9 || self.ac {
+                    correction |= 0x06;
+                }
+                if self.a > 0x99 || self.cy {
+                    correction |= 0x60;
+                    self.cy = true;
+                }
+                let ac = (self.a & 0x0f) + (correction & 0x0f) > 0x0f;
+                self.a = self.a.wrapping_add(correction);
+                flag!(self, self.a);
+                self.ac = ac;
+                self.history.push("DAA".to_string());
+            }
+            0x29 => {
+                let (hl, overflow) = self.hl().overflowing_add(self.hl());
+                self.set_hl(hl);
+                self.cy = overflow;
+                self.history.push("DAD H".to_string());
+            }
+            0x2a => {
+                let addr = operand;
+                self.set_hl(self.read_word(addr));
+                self.history.push(format!("LHLD {:#06x}", addr));
+            }
+            0x2b => {
+                self.set_hl(self.hl().wrapping_sub(1));
+                self.history.push("DCX H".to_string());
+            }
+            0x2c => {
+                self.l = self.inr(self.l);
+                self.history.push("INR L".to_string());
+            }
+            0x2d => {
+                self.l = self.dcr(self.l);
+                self.history.push("DCR L".to_string());
+            }
+            0x2e => {
+                self.l = operand as u8;
+                self.history.push(format!("MVI L, {:#04x}", self.l));
+            }
+            0x2f => {
+                // the one ALU op that leaves every flag alone
+                self.a = !self.a;
+                self.history.push("CMA".to_string());
+            }
+            0x31 => {
+                self.sp = operand;
+                self.history.push(format!("LXI SP, {:#06x}", self.sp));
+            }
+            0x32 => {
+                let addr = operand;
+                self.write(addr, self.a);
+                self.history.push(format!("STA {:#06x}", addr));
+            }
+            0x33 => {
+                self.sp = self.sp.wrapping_add(1);
+                self.history.push("INX SP".to_string());
+            }
+            0x34 => {
+                let addr = self.hl();
+                let value = self.inr(self.read(addr));
+                self.write(addr, value);
+                self.history.push("INR M".to_string());
+            }
+            0x35 => {
+                let addr = self.hl();
+                let value = self.dcr(self.read(addr));
+                self.write(addr, value);
+                self.history.push("DCR M".to_string());
+            }
+            0x36 => {
+                let value = operand as u8;
+                self.write(self.hl(), value);
+                self.history.push(format!("MVI M, {:#04x}", value));
+            }
+            0x37 => {
+                self.cy = true;
+                self.history.push("STC".to_string());
+            }
+            0x39 => {
+                let (hl, overflow) = self.hl().overflowing_add(self.sp);
+                self.set_hl(hl);
+                self.cy = overflow;
+                self.history.push("DAD SP".to_string());
+            }
+            0x3a => {
+                let addr = operand;
+                self.a = self.read(addr);
+                self.history.push(format!("LDA {:#06x}", addr));
+            }
+            0x3b => {
+                self.sp = self.sp.wrapping_sub(1);
+                self.history.push("DCX SP".to_string());
+            }
+            0x3c => {
+                self.a = self.inr(self.a);
+                self.history.push("INR A".to_string());
+            }
+            0x3d => {
+                self.a = self.dcr(self.a);
+                self.history.push("DCR A".to_string());
+            }
+            0x3e => {
+                self.a = operand as u8;
+                self.history.push(format!("MVI A, {:#04x}", self.a));
+            }
+            0x3f => {
+                self.cy = !self.cy;
+                self.history.push("CMC".to_string());
+            }
+            0x40 => {
+                self.history.push("MOV B, B".to_string());
+            }
+            0x41 => {
+                self.b = self.c;
+                self.history.push("MOV B, C".to_string());
+            }
+            0x42 => {
+                self.b = self.d;
+                self.history.push("MOV B, D".to_string());
+            }
+            0x43 => {
+                self.b = self.e;
+                self.history.push("MOV B, E".to_string());
+            }
+            0x44 => {
+                self.b = self.h;
+                self.history.push("MOV B, H".to_string());
+            }
+            0x45 => {
+                self.b = self.l;
+                self.history.push("MOV B, L".to_string());
+            }
+            0x46 => {
+                self.b = self.read(self.hl());
+                self.history.push("MOV B, M".to_string());
+            }
+            0x47 => {
+                self.b = self.a;
+                self.history.push("MOV B, A".to_string());
+            }
+            0x48 => {
+                self.c = self.b;
+                self.history.push("MOV C, B".to_string());
+            }
+            0x49 => {
+                self.history.push("MOV C, C".to_string());
+            }
+            0x4a => {
+                self.c = self.d;
+                self.history.push("MOV C, D".to_string());
+            }
+            0x4b => {
+                self.c = self.e;
+                self.history.push("MOV C, E".to_string());
+            }
+            0x4c => {
+                self.c = self.h;
+                self.history.push("MOV C, H".to_string());
+            }
+            0x4d => {
+                self.c = self.l;
+                self.history.push("MOV C, L".to_string());
+            }
+            0x4e => {
+                self.c = self.read(self.hl());
+                self.history.push("MOV C, M".to_string());
+            }
+            0x4f => {
+                self.c = self.a;
+                self.history.push("MOV C, A".to_string());
+            }
+            0x50 => {
+                self.d = self.b;
+                self.history.push("MOV D, B".to_string());
+            }
+            0x51 => {
+                self.d = self.c;
+                self.history.push("MOV D, C".to_string());
+            }
+            0x52 => {
+                self.history.push("MOV D, D".to_string());
+            }
+            0x53 => {
+                self.d = self.e;
+                self.history.push("MOV D, E".to_string());
+            }
+            0x54 => {
+                self.d = self.h;
+                self.history.push("MOV D, H".to_string());
+            }
+            0x55 => {
+                self.d = self.l;
+                self.history.push("MOV D, L".to_string());
+            }
+            0x56 => {
+                self.d = self.read(self.hl());
+                self.history.push("MOV D, M".to_string());
+            }
+            0x57 => {
+                self.d = self.a;
+                self.history.push("MOV D, A".to_string());
+            }
+            0x58 => {
+                self.e = self.b;
+                self.history.push("MOV E, B".to_string());
+            }
+            0x59 => {
+                self.e = self.c;
+                self.history.push("MOV E, C".to_string());
+            }
+            0x5a => {
+                self.e = self.d;
+                self.history.push("MOV E, D".to_string());
+            }
+            0x5b => {
+                self.history.push("MOV E, E".to_string());
+            }
+            0x5c => {
+                self.e = self.h;
+                self.history.push("MOV E, H".to_string());
+            }
+            0x5d => {
+                self.e = self.l;
+                self.history.push("MOV E, L".to_string());
+            }
+            0x5e => {
+                self.e = self.read(self.hl());
+                self.history.push("MOV E, M".to_string());
+            }
+            0x5f => {
+                self.e = self.a;
+                self.history.push("MOV E, A".to_string());
+            }
+            0x60 => {
+                self.h = self.b;
+                self.history.push("MOV H, B".to_string());
+            }
+            0x61 => {
+                self.h = self.c;
+                self.history.push("MOV H, C".to_string());
+            }
+            0x62 => {
+                self.h = self.d;
+                self.history.push("MOV H, D".to_string());
+            }
+            0x63 => {
+                self.h = self.e;
+                self.history.push("MOV H, E".to_string());
+            }
+            0x64 => {
+                self.history.push("MOV H, H".to_string());
+            }
+            0x65 => {
+                self.h = self.l;
+                self.history.push("MOV H, L".to_string());
+            }
+            0x66 => {
+                self.h = self.read(self.hl());
+                self.history.push("MOV H, M".to_string());
+            }
+            0x67 => {
+                self.h = self.a;
+                self.history.push("MOV H, A".to_string());
+            }
+            0x68 => {
+                self.l = self.b;
+                self.history.push("MOV L, B".to_string());
+            }
+            0x69 => {
+                self.l = self.c;
+                self.history.push("MOV L, C".to_string());
+            }
+            0x6a => {
+                self.l = self.d;
+                self.history.push("MOV L, D".to_string());
+            }
+            0x6b => {
+                self.l = self.e;
+                self.history.push("MOV L, E".to_string());
+            }
+            0x6c => {
+                self.l = self.h;
+                self.history.push("MOV L, H".to_string());
+            }
+            0x6d => {
+                self.history.push("MOV L, L".to_string());
+            }
+            0x6e => {
+                self.l = self.read(self.hl());
+                self.history.push("MOV L, M".to_string());
+            }
+            0x6f => {
+                self.l = self.a;
+                self.history.push("MOV L, A".to_string());
+            }
+            0x70 => {
+                self.write(self.hl(), self.b);
+                self.history.push("MOV M, B".to_string());
+            }
+            0x71 => {
+                self.write(self.hl(), self.c);
+                self.history.push("MOV M, C".to_string());
+            }
+            0x72 => {
+                self.write(self.hl(), self.d);
+                self.history.push("MOV M, D".to_string());
+            }
+            0x73 => {
+                self.write(self.hl(), self.e);
+                self.history.push("MOV M, E".to_string());
+            }
+            0x74 => {
+                self.write(self.hl(), self.h);
+                self.history.push("MOV M, H".to_string());
+            }
+            0x75 => {
+                self.write(self.hl(), self.l);
+                self.history.push("MOV M, L".to_string());
+            }
+            0x76 => {
+                self.halt = true;
+                self.history.push("HLT".to_string());
+            }
+            0x77 => {
+                self.write(self.hl(), self.a);
+                self.history.push("MOV M, A".to_string());
+            }
+            0x78 => {
+                self.a = self.b;
+                self.history.push("MOV A, B".to_string());
+            }
+            0x79 => {
+                self.a = self.c;
+                self.history.push("MOV A, C".to_string());
+            }
+            0x7a => {
+                self.a = self.d;
+                self.history.push("MOV A, D".to_string());
+            }
+            0x7b => {
+                self.a = self.e;
+                self.history.push("MOV A, E".to_string());
+            }
+            0x7c => {
+                self.a = self.h;
+                self.history.push("MOV A, H".to_string());
+            }
+            0x7d => {
+                self.a = self.l;
+                self.history.push("MOV A, L".to_string());
+            }
+            0x7e => {
+                self.a = self.read(self.hl());
+                self.history.push("MOV A, M".to_string());
+            }
+            0x7f => {
+                self.history.push("MOV A, A".to_string());
+            }
+            0x80 => {
+                self.a = self.add(self.b, false);
+                self.history.push("ADD B".to_string());
+            }
+            0x81 => {
+                self.a = self.add(self.c, false);
+                self.history.push("ADD C".to_string());
+            }
+            0x82 => {
+                self.a = self.add(self.d, false);
+                self.history.push("ADD D".to_string());
+            }
+            0x83 => {
+                self.a = self.add(self.e, false);
+                self.history.push("ADD E".to_string());
+            }
+            0x84 => {
+                self.a = self.add(self.h, false);
+                self.history.push("ADD H".to_string());
+            }
+            0x85 => {
+                self.a = self.add(self.l, false);
+                self.history.push("ADD L".to_string());
+            }
+            0x86 => {
+                let value = self.read(self.hl());
+                self.a = self.add(value, false);
+                self.history.push("ADD M".to_string());
+            }
+            0x87 => {
+                self.a = self.add(self.a, false);
+                self.history.push("ADD A".to_string());
+            }
+            0x88 => {
+                self.a = self.add(self.b, self.cy);
+                self.history.push("ADC B".to_string());
+            }
+            0x89 => {
+                self.a = self.add(self.c, self.cy);
+                self.history.push("ADC C".to_string());
+            }
+            0x8a => {
+                self.a = self.add(self.d, self.cy);
+                self.history.push("ADC D".to_string());
+            }
+            0x8b => {
+                self.a = self.add(self.e, self.cy);
+                self.history.push("ADC E".to_string());
+            }
+            0x8c => {
+                self.a = self.add(self.h, self.cy);
+                self.history.push("ADC H".to_string());
+            }
+            0x8d => {
+                self.a = self.add(self.l, self.cy);
+                self.history.push("ADC L".to_string());
+            }
+            0x8e => {
+                let value = self.read(self.hl());
+                self.a = self.add(value, self.cy);
+                self.history.push("ADC M".to_string());
+            }
+            0x8f => {
+                self.a = self.add(self.a, self.cy);
+                self.history.push("ADC A".to_string());
+            }
+            0x90 => {
+                self.a = self.sub(self.b, false);
+                self.history.push("SUB B".to_string());
+            }
+            0x91 => {
+                self.a = self.sub(self.c, false);
+                self.history.push("SUB C".to_string());
+            }
+            0x92 => {
+                self.a = self.sub(self.d, false);
+                self.history.push("SUB D".to_string());
+            }
+            0x93 => {
+                self.a = self.sub(self.e, false);
+                self.history.push("SUB E".to_string());
+            }
+            0x94 => {
+                self.a = self.sub(self.h, false);
+                self.history.push("SUB H".to_string());
+            }
+            0x95 => {
+                self.a = self.sub(self.l, false);
+                self.history.push("SUB L".to_string());
+            }
+            0x96 => {
+                let value = self.read(self.hl());
+                self.a = self.sub(value, false);
+                self.history.push("SUB M".to_string());
+            }
+            0x97 => {
+                self.a = self.sub(self.a, false);
+                self.history.push("SUB A".to_string());
+            }
+            0x98 => {
+                self.a = self.sub(self.b, self.cy);
+                self.history.push("SBB B".to_string());
+            }
+            0x99 => {
+                self.a = self.sub(self.c, self.cy);
+                self.history.push("SBB C".to_string());
+            }
+            0x9a => {
+                self.a = self.sub(self.d, self.cy);
+                self.history.push("SBB D".to_string());
+            }
+            0x9b => {
+                self.a = self.sub(self.e, self.cy);
+                self.history.push("SBB E".to_string());
+            }
+            0x9c => {
+                self.a = self.sub(self.h, self.cy);
+                self.history.push("SBB H".to_string());
+            }
+            0x9d => {
+                self.a = self.sub(self.l, self.cy);
+                self.history.push("SBB L".to_string());
+            }
+            0x9e => {
+                let value = self.read(self.hl());
+                self.a = self.sub(value, self.cy);
+                self.history.push("SBB M".to_string());
+            }
+            0x9f => {
+                self.a = self.sub(self.a, self.cy);
+                self.history.push("SBB A".to_string());
+            }
+            0xa0 => {
+                let ac = (self.a | self.b) & 0x08 != 0;
+                self.a &= self.b;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = ac;
+                self.history.push("ANA B".to_string());
+            }
+            0xa1 => {
+                let ac = (self.a | self.c) & 0x08 != 0;
+                self.a &= self.c;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = ac;
+                self.history.push("ANA C".to_string());
+            }
+            0xa2 => {
+                let ac = (self.a | self.d) & 0x08 != 0;
+                self.a &= self.d;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = ac;
+                self.history.push("ANA D".to_string());
+            }
+            0xa3 => {
+                let ac = (self.a | self.e) & 0x08 != 0;
+                self.a &= self.e;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = ac;
+                self.history.push("ANA E".to_string());
+            }
+            0xa4 => {
+                let ac = (self.a | self.h) & 0x08 != 0;
+                self.a &= self.h;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = ac;
+                self.history.push("ANA H".to_string());
+            }
+            0xa5 => {
+                let ac = (self.a | self.l) & 0x08 != 0;
+                self.a &= self.l;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = ac;
+                self.history.push("ANA L".to_string());
+            }
+            0xa6 => {
+                let value = self.read(self.hl());
+                let ac = (self.a | value) & 0x08 != 0;
+                self.a &= value;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = ac;
+                self.history.push("ANA M".to_string());
+            }
+            0xa7 => {
+                let ac = self.a & 0x08 != 0;
+                self.a &= self.a;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = ac;
+                self.history.push("ANA A".to_string());
+            }
+            0xa8 => {
+                self.a ^= self.b;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("XRA B".to_string());
+            }
+            0xa9 => {
+                self.a ^= self.c;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("XRA C".to_string());
+            }
+            0xaa => {
+                self.a ^= self.d;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("XRA D".to_string());
+            }
+            0xab => {
+                self.a ^= self.e;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("XRA E".to_string());
+            }
+            0xac => {
+                self.a ^= self.h;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("XRA H".to_string());
+            }
+            0xad => {
+                self.a ^= self.l;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("XRA L".to_string());
+            }
+            0xae => {
+                let value = self.read(self.hl());
+                self.a ^= value;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("XRA M".to_string());
+            }
+            0xaf => {
+                self.a ^= self.a;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("XRA A".to_string());
+            }
+            0xb0 => {
+                self.a |= self.b;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("ORA B".to_string());
+            }
+            0xb1 => {
+                self.a |= self.c;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("ORA C".to_string());
+            }
+            0xb2 => {
+                self.a |= self.d;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("ORA D".to_string());
+            }
+            0xb3 => {
+                self.a |= self.e;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("ORA E".to_string());
+            }
+            0xb4 => {
+                self.a |= self.h;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("ORA H".to_string());
+            }
+            0xb5 => {
+                self.a |= self.l;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("ORA L".to_string());
+            }
+            0xb6 => {
+                let value = self.read(self.hl());
+                self.a |= value;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("ORA M".to_string());
+            }
+            0xb7 => {
+                self.a |= self.a;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push("ORA A".to_string());
+            }
+            // CMP and CPI are a SUB that throws the difference away: every
+            // flag, s and p included, describes `a - operand`
+            0xb8 => {
+                self.sub(self.b, false);
+                self.history.push("CMP B".to_string());
+            }
+            0xb9 => {
+                self.sub(self.c, false);
+                self.history.push("CMP C".to_string());
+            }
+            0xba => {
+                self.sub(self.d, false);
+                self.history.push("CMP D".to_string());
+            }
+            0xbb => {
+                self.sub(self.e, false);
+                self.history.push("CMP E".to_string());
+            }
+            0xbc => {
+                self.sub(self.h, false);
+                self.history.push("CMP H".to_string());
+            }
+            0xbd => {
+                self.sub(self.l, false);
+                self.history.push("CMP L".to_string());
+            }
+            0xbe => {
+                let value = self.read(self.hl());
+                self.sub(value, false);
+                self.history.push("CMP M".to_string());
+            }
+            0xbf => {
+                self.sub(self.a, false);
+                self.history.push("CMP A".to_string());
+            }
+            0xc0 => {
+                if !self.z {
+                    self.ret();
+                }
+                self.history.push("RNZ".to_string());
+            }
+            0xc1 => {
+                let bc = self.pop();
+                self.set_bc(bc);
+                self.history.push("POP B".to_string());
+            }
+            0xc2 => {
+                let addr = operand;
+                if !self.z {
+                    self.pc = addr;
+                }
+                self.history.push(format!("JNZ {:#06x}", addr));
+            }
+            0xc3 | 0xcb => {
+                let addr = operand;
+                self.pc = addr;
+                self.history.push(format!("JMP {:#06x}", addr));
+            }
+            0xc4 => {
+                let addr = operand;
+                if !self.z {
+                    self.call(addr);
+                }
+                self.history.push(format!("CNZ {:#06x}", addr));
+            }
+            0xc5 => {
+                self.push(self.bc());
+                self.history.push("PUSH B".to_string());
+            }
+            0xc6 => {
+                let value = operand as u8;
+                self.a = self.add(value, false);
+                self.history.push(format!("ADI {:#04x}", value));
+            }
+            0xc7 => {
+                self.rst(0);
+                self.history.push("RST 0".to_string());
+            }
+            0xc8 => {
+                if self.z {
+                    self.ret();
+                }
+                self.history.push("RZ".to_string());
+            }
+            0xc9 | 0xd9 => {
+                self.ret();
+                self.history.push("RET".to_string());
+            }
+            0xca => {
+                let addr = operand;
+                if self.z {
+                    self.pc = addr;
+                }
+                self.history.push(format!("JZ {:#06x}", addr));
+            }
+            0xcc => {
+                let addr = operand;
+                if self.z {
+                    self.call(addr);
+                }
+                self.history.push(format!("CZ {:#06x}", addr));
+            }
+            0xcd | 0xdd | 0xed | 0xfd => {
+                let addr = operand;
+                self.call(addr);
+                self.history.push(format!("CALL {:#06x}", addr));
+            }
+            0xce => {
+                let value = operand as u8;
+                self.a = self.add(value, self.cy);
+                self.history.push(format!("ACI {:#04x}", value));
+            }
+            0xcf => {
+                self.rst(1);
+                self.history.push("RST 1".to_string());
+            }
+            0xd0 => {
+                if !self.cy {
+                    self.ret();
+                }
+                self.history.push("RNC".to_string());
+            }
+            0xd1 => {
+                let de = self.pop();
+                self.set_de(de);
+                self.history.push("POP D".to_string());
+            }
+            0xd2 => {
+                let addr = operand;
+                if !self.cy {
+                    self.pc = addr;
+                }
+                self.history.push(format!("JNC {:#06x}", addr));
+            }
+            0xd3 => {
+                let port = operand as u8;
+                io.output(port, self.a);
+                self.log_port(IoDirection::Out, port, self.a);
+                self.history.push(format!("OUT {:#04x}", port));
+            }
+            0xd4 => {
+                let addr = operand;
+                if !self.cy {
+                    self.call(addr);
+                }
+                self.history.push(format!("CNC {:#06x}", addr));
+            }
+            0xd5 => {
+                self.push(self.de());
+                self.history.push("PUSH D".to_string());
+            }
+            0xd6 => {
+                let value = operand as u8;
+                self.a = self.sub(value, false);
+                self.history.push(format!("SUI {:#04x}", value));
+            }
+            0xd7 => {
+                self.rst(2);
+                self.history.push("RST 2".to_string());
+            }
+            0xd8 => {
+                if self.cy {
+                    self.ret();
+                }
+                self.history.push("RC".to_string());
+            }
+            0xda => {
+                let addr = operand;
+                if self.cy {
+                    self.pc = addr;
+                }
+                self.history.push(format!("JC {:#06x}", addr));
+            }
+            0xdb => {
+                let port = operand as u8;
+                self.a = io.input(port);
+                self.log_port(IoDirection::In, port, self.a);
+                self.rewind_input(self.a);
+                self.history.push(format!("IN {:#04x}", port));
+            }
+            0xdc => {
+                let addr = operand;
+                if self.cy {
+                    self.call(addr);
+                }
+                self.history.push(format!("CC {:#06x}", addr));
+            }
+            0xde => {
+                let value = operand as u8;
+                self.a = self.sub(value, self.cy);
+                self.history.push(format!("SBI {:#04x}", value));
+            }
+            0xdf => {
+                self.rst(3);
+                self.history.push("RST 3".to_string());
+            }
+            0xe0 => {
+                if !self.p {
+                    self.ret();
+                }
+                self.history.push("RPO".to_string());
+            }
+            0xe1 => {
+                let hl = self.pop();
+                self.set_hl(hl);
+                self.history.push("POP H".to_string());
+            }
+            0xe2 => {
+                let addr = operand;
+                if !self.p {
+                    self.pc = addr;
+                }
+                self.history.push(format!("JPO {:#06x}", addr));
+            }
+            0xe3 => {
+                // swap in place, sp itself never moves
+                let sp = self.sp;
+                let (l, h) = (self.read(sp), self.read(sp.wrapping_add(1)));
+                self.write(sp, self.l);
+                self.write(sp.wrapping_add(1), self.h);
+                self.l = l;
+                self.h = h;
+                self.history.push("XTHL".to_string());
+            }
+            0xe4 => {
+                let addr = operand;
+                if !self.p {
+                    self.call(addr);
+                }
+                self.history.push(format!("CPO {:#06x}", addr));
+            }
+            0xe5 => {
+                self.push(self.hl());
+                self.history.push("PUSH H".to_string());
+            }
+            0xe6 => {
+                let value = operand as u8;
+                let ac = (self.a | value) & 0x08 != 0;
+                self.a &= value;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = ac;
+                self.history.push(format!("ANI {:#04x}", value));
+            }
+            0xe7 => {
+                self.rst(4);
+                self.history.push("RST 4".to_string());
+            }
+            0xe8 => {
+                if self.p {
+                    self.ret();
+                }
+                self.history.push("RPE".to_string());
+            }
+            0xe9 => {
+                self.pc = self.hl();
+                self.history.push("PCHL".to_string());
+            }
+            0xea => {
+                let addr = operand;
+                if self.p {
+                    self.pc = addr;
+                }
+                self.history.push(format!("JPE {:#06x}", addr));
+            }
+            0xeb => {
+                let de = self.de();
+                self.set_de(self.hl());
+                self.set_hl(de);
+                self.history.push("XCHG".to_string());
+            }
+            0xec => {
+                let addr = operand;
+                if self.p {
+                    self.call(addr);
+                }
+                self.history.push(format!("CPE {:#06x}", addr));
+            }
+            0xee => {
+                let value = operand as u8;
+                self.a ^= value;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push(format!("XRI {:#04x}", value));
+            }
+            0xef => {
+                self.rst(5);
+                self.history.push("RST 5".to_string());
+            }
+            0xf0 => {
+                if !self.s {
+                    self.ret();
+                }
+                self.history.push("RP".to_string());
+            }
+            0xf1 => {
+                let value = self.pop();
+                self.a = (value >> 8) as u8;
+                self.set_flags_byte(value as u8);
+                self.history.push("POP PSW".to_string());
+            }
+            0xf2 => {
+                let addr = operand;
+                if !self.s {
+                    self.pc = addr;
+                }
+                self.history.push(format!("JP {:#06x}", addr));
+            }
+            0xf3 => {
+                self.interrupt = false;
+                self.ei_pending = false;
+                self.history.push("DI".to_string());
+            }
+            0xf4 => {
+                let addr = operand;
+                if !self.s {
+                    self.call(addr);
+                }
+                self.history.push(format!("CP {:#06x}", addr));
+            }
+            0xf5 => {
+                self.push((self.a as u16) << 8 | self.flags_byte() as u16);
+                self.history.push("PUSH PSW".to_string());
+            }
+            0xf6 => {
+                let value = operand as u8;
+                self.a |= value;
+                flag!(self, self.a);
+                self.cy = false;
+                self.ac = false;
+                self.history.push(format!("ORI {:#04x}", value));
+            }
+            0xf7 => {
+                self.rst(6);
+                self.history.push("RST 6".to_string());
+            }
+            0xf8 => {
+                if self.s {
+                    self.ret();
+                }
+                self.history.push("RM".to_string());
+            }
+            0xf9 => {
+                self.sp = self.hl();
+                self.history.push("SPHL".to_string());
+            }
+            0xfa => {
+                let addr = operand;
+                if self.s {
+                    self.pc = addr;
+                }
+                self.history.push(format!("JM {:#06x}", addr));
+            }
+            0xfb => {
+                self.ei_pending = true;
+                self.history.push("EI".to_string());
+            }
+            0xfc => {
+                let addr = operand;
+                if self.s {
+                    self.call(addr);
+                }
+                self.history.push(format!("CM {:#06x}", addr));
+            }
+            0xfe => {
+                let value = operand as u8;
+                self.sub(value, false);
+                self.history.push(format!("CPI {:#04x}", value));
+            }
+            0xff => {
+                self.rst(7);
+                self.history.push("RST 7".to_string());
+            }
+        }
+    }
+}
@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+
+use crate::bus::Bus;
+use crate::cpu::{Cpu8080, CpuError, ILLEGAL_OPCODES, OPCODES};
+
+/// `disassembler` for callers that can't promise `pc` is inside `rom`
+pub fn try_disassemble(pc: usize, rom: &[u8]) -> Result<(String, usize), CpuError> {
+    match pc < rom.len() {
+        true => Ok(disassembler(pc, rom)),
+        false => Err(CpuError::OutOfRange { pc, len: rom.len() }),
+    }
+}
+
+/// text and next address of the instruction at `pc`, which has to be
+/// inside `rom`
+pub fn disassembler(pc: usize, rom: &[u8]) -> (String, usize) {
+    // an instruction cut off by the end of the rom is only data
+    if pc + instruction_length(rom[pc]) > rom.len() {
+        return (format!("db {:#04x}", rom[pc]), pc + 1);
+    }
+    match rom[pc] {
+        0x00 => ("NOP".to_string(), pc + 1),
+        0x01 => (
+            format!("LXI B, {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0x02 => ("STAX B".to_string(), pc + 1),
+        0x03 => ("INX B".to_string(), pc + 1),
+        0x04 => ("INR B".to_string(), pc + 1),
+        0x05 => ("DCR B".to_string(), pc + 1),
+        0x06 => (format!("MVI B, {:#04x}", rom[pc + 1]), pc + 2),
+        0x07 => ("RLC".to_string(), pc + 1),
+        0x08 => ("NOP".to_string(), pc + 1),
+        0x09 => ("DAD B".to_string(), pc + 1),
+        0x0a => ("LDAX B".to_string(), pc + 1),
+        0x0b => ("DCX B".to_string(), pc + 1),
+        0x0c => ("INR C".to_string(), pc + 1),
+        0x0d => ("DCR C".to_string(), pc + 1),
+        0x0e => (format!("MVI C, {:#04x}", rom[pc + 1]), pc + 2),
+        0x0f => ("RRC".to_string(), pc + 1),
+        0x10 => ("NOP".to_string(), pc + 1),
+        0x11 => (
+            format!("LXI D, {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0x12 => ("STAX D".to_string(), pc + 1),
+        0x13 => ("INX D".to_string(), pc + 1),
+        0x14 => ("INR D".to_string(), pc + 1),
+        0x15 => ("DCR D".to_string(), pc + 1),
+        0x16 => (format!("MVI D, {:#04x}", rom[pc + 1]), pc + 2),
+        0x17 => ("RAL".to_string(), pc + 1),
+        0x18 => ("NOP".to_string(), pc + 1),
+        0x19 => ("DAD D".to_string(), pc + 1),
+        0x1a => ("LDAX D".to_string(), pc + 1),
+        0x1b => ("DCX D".to_string(), pc + 1),
+        0x1c => ("INR E".to_string(), pc + 1),
+        0x1d => ("DCR E".to_string(), pc + 1),
+        0x1e => (format!("MVI E, {:#04x}", rom[pc + 1]), pc + 2),
+        0x1f => ("RAR".to_string(), pc + 1),
+        0x20 => ("NOP".to_string(), pc + 1),
+        0x21 => (
+            format!("LXI H, {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0x22 => (
+            format!("SHLD {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0x23 => ("INX H".to_string(), pc + 1),
+        0x24 => ("INR H".to_string(), pc + 1),
+        0x25 => ("DCR H".to_string(), pc + 1),
+        0x26 => (format!("MVI H, {:#04x}", rom[pc + 1]), pc + 2),
+        0x27 => ("DAA".to_string(), pc + 1),
+        0x28 => ("NOP".to_string(), pc + 1),
+        0x29 => ("DAD H".to_string(), pc + 1),
+        0x2a => (
+            format!("LHLD {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0x2b => ("DCX H".to_string(), pc + 1),
+        0x2c => ("INR L".to_string(), pc + 1),
+        0x2d => ("DCR L".to_string(), pc + 1),
+        0x2e => (format!("MVI L, {:#04x}", rom[pc + 1]), pc + 2),
+        0x2f => ("CMA".to_string(), pc + 1),
+        0x30 => ("NOP".to_string(), pc + 1),
+        0x31 => (
+            format!("LXI SP, {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0x32 => (
+            format!("STA {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0x33 => ("INX SP".to_string(), pc + 1),
+        0x34 => ("INR M".to_string(), pc + 1),
+        0x35 => ("DCR M".to_string(), pc + 1),
+        0x36 => (format!("MVI M, {:#04x}", rom[pc + 1]), pc + 2),
+        0x37 => ("STC".to_string(), pc + 1),
+        0x38 => ("NOP".to_string(), pc + 1),
+        0x39 => ("DAD SP".to_string(), pc + 1),
+        0x3a => (
+            format!("LDA {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0x3b => ("DCX SP".to_string(), pc + 1),
+        0x3c => ("INR A".to_string(), pc + 1),
+        0x3d => ("DCR A".to_string(), pc + 1),
+        0x3e => (format!("MVI A, {:#04x}", rom[pc + 1]), pc + 2),
+        0x3f => ("CMC".to_string(), pc + 1),
+        0x40 => ("MOV B, B".to_string(), pc + 1),
+        0x41 => ("MOV B, C".to_string(), pc + 1),
+        0x42 => ("MOV B, D".to_string(), pc + 1),
+        0x43 => ("MOV B, E".to_string(), pc + 1),
+        0x44 => ("MOV B, H".to_string(), pc + 1),
+        0x45 => ("MOV B, L".to_string(), pc + 1),
+        0x46 => ("MOV B, M".to_string(), pc + 1),
+        0x47 => ("MOV B, A".to_string(), pc + 1),
+        0x48 => ("MOV C, B".to_string(), pc + 1),
+        0x49 => ("MOV C, C".to_string(), pc + 1),
+        0x4a => ("MOV C, D".to_string(), pc + 1),
+        0x4b => ("MOV C, E".to_string(), pc + 1),
+        0x4c => ("MOV C, H".to_string(), pc + 1),
+        0x4d => ("MOV C, L".to_string(), pc + 1),
+        0x4e => ("MOV C, M".to_string(), pc + 1),
+        0x4f => ("MOV C, A".to_string(), pc + 1),
+        0x50 => ("MOV D, B".to_string(), pc + 1),
+        0x51 => ("MOV D, C".to_string(), pc + 1),
+        0x52 => ("MOV D, D".to_string(), pc + 1),
+        0x53 => ("MOV D, E".to_string(), pc + 1),
+        0x54 => ("MOV D, H".to_string(), pc + 1),
+        0x55 => ("MOV D, L".to_string(), pc + 1),
+        0x56 => ("MOV D, M".to_string(), pc + 1),
+        0x57 => ("MOV D, A".to_string(), pc + 1),
+        0x58 => ("MOV E, B".to_string(), pc + 1),
+        0x59 => ("MOV E, C".to_string(), pc + 1),
+        0x5a => ("MOV E, D".to_string(), pc + 1),
+        0x5b => ("MOV E, E".to_string(), pc + 1),
+        0x5c => ("MOV E, H".to_string(), pc + 1),
+        0x5d => ("MOV E, L".to_string(), pc + 1),
+        0x5e => ("MOV E, M".to_string(), pc + 1),
+        0x5f => ("MOV E, A".to_string(), pc + 1),
+        0x60 => ("MOV H, B".to_string(), pc + 1),
+        0x61 => ("MOV H, C".to_string(), pc + 1),
+        0x62 => ("MOV H, D".to_string(), pc + 1),
+        0x63 => ("MOV H, E".to_string(), pc + 1),
+        0x64 => ("MOV H, H".to_string(), pc + 1),
+        0x65 => ("MOV H, L".to_string(), pc + 1),
+        0x66 => ("MOV H, M".to_string(), pc + 1),
+        0x67 => ("MOV H, A".to_string(), pc + 1),
+        0x68 => ("MOV L, B".to_string(), pc + 1),
+        0x69 => ("MOV L, C".to_string(), pc + 1),
+        0x6a => ("MOV L, D".to_string(), pc + 1),
+        0x6b => ("MOV L, E".to_string(), pc + 1),
+        0x6c => ("MOV L, H".to_string(), pc + 1),
+        0x6d => ("MOV L, L".to_string(), pc + 1),
+        0x6e => ("MOV L, M".to_string(), pc + 1),
+        0x6f => ("MOV L, A".to_string(), pc + 1),
+        0x70 => ("MOV M, B".to_string(), pc + 1),
+        0x71 => ("MOV M, C".to_string(), pc + 1),
+        0x72 => ("MOV M, D".to_string(), pc + 1),
+        0x73 => ("MOV M, E".to_string(), pc + 1),
+        0x74 => ("MOV M, H".to_string(), pc + 1),
+        0x75 => ("MOV M, L".to_string(), pc + 1),
+        0x76 => ("HLT".to_string(), pc + 1),
+        0x77 => ("MOV M, A".to_string(), pc + 1),
+        0x78 => ("MOV A, B".to_string(), pc + 1),
+        0x79 => ("MOV A, C".to_string(), pc + 1),
+        0x7a => ("MOV A, D".to_string(), pc + 1),
+        0x7b => ("MOV A, E".to_string(), pc + 1),
+        0x7c => ("MOV A, H".to_string(), pc + 1),
+        0x7d => ("MOV A, L".to_string(), pc + 1),
+        0x7e => ("MOV A, M".to_string(), pc + 1),
+        0x7f => ("MOV A, A".to_string(), pc + 1),
+        0x80 => ("ADD B".to_string(), pc + 1),
+        0x81 => ("ADD C".to_string(), pc + 1),
+        0x82 => ("ADD D".to_string(), pc + 1),
+        0x83 => ("ADD E".to_string(), pc + 1),
+        0x84 => ("ADD H".to_string(), pc + 1),
+        0x85 => ("ADD L".to_string(), pc + 1),
+        0x86 => ("ADD M".to_string(), pc + 1),
+        0x87 => ("ADD A".to_string(), pc + 1),
+        0x88 => ("ADC B".to_string(), pc + 1),
+        0x89 => ("ADC C".to_string(), pc + 1),
+        0x8a => ("ADC D".to_string(), pc + 1),
+        0x8b => ("ADC E".to_string(), pc + 1),
+        0x8c => ("ADC H".to_string(), pc + 1),
+        0x8d => ("ADC L".to_string(), pc + 1),
+        0x8e => ("ADC M".to_string(), pc + 1),
+        0x8f => ("ADC A".to_string(), pc + 1),
+        0x90 => ("SUB B".to_string(), pc + 1),
+        0x91 => ("SUB C".to_string(), pc + 1),
+        0x92 => ("SUB D".to_string(), pc + 1),
+        0x93 => ("SUB E".to_string(), pc + 1),
+        0x94 => ("SUB H".to_string(), pc + 1),
+        0x95 => ("SUB L".to_string(), pc + 1),
+        0x96 => ("SUB M".to_string(), pc + 1),
+        0x97 => ("SUB A".to_string(), pc + 1),
+        0x98 => ("SBB B".to_string(), pc + 1),
+        0x99 => ("SBB C".to_string(), pc + 1),
+        0x9a => ("SBB D".to_string(), pc + 1),
+        0x9b => ("SBB E".to_string(), pc + 1),
+        0x9c => ("SBB H".to_string(), pc + 1),
+        0x9d => ("SBB L".to_string(), pc + 1),
+        0x9e => ("SBB M".to_string(), pc + 1),
+        0x9f => ("SBB A".to_string(), pc + 1),
+        0xa0 => ("ANA B".to_string(), pc + 1),
+        0xa1 => ("ANA C".to_string(), pc + 1),
+        0xa2 => ("ANA D".to_string(), pc + 1),
+        0xa3 => ("ANA E".to_string(), pc + 1),
+        0xa4 => ("ANA H".to_string(), pc + 1),
+        0xa5 => ("ANA L".to_string(), pc + 1),
+        0xa6 => ("ANA M".to_string(), pc + 1),
+        0xa7 => ("ANA A".to_string(), pc + 1),
+        0xa8 => ("XRA B".to_string(), pc + 1),
+        0xa9 => ("XRA C".to_string(), pc + 1),
+        0xaa => ("XRA D".to_string(), pc + 1),
+        0xab => ("XRA E".to_string(), pc + 1),
+        0xac => ("XRA H".to_string(), pc + 1),
+        0xad => ("XRA L".to_string(), pc + 1),
+        0xae => ("XRA M".to_string(), pc + 1),
+        0xaf => ("XRA A".to_string(), pc + 1),
+        0xb0 => ("ORA B".to_string(), pc + 1),
+        0xb1 => ("ORA C".to_string(), pc + 1),
+        0xb2 => ("ORA D".to_string(), pc + 1),
+        0xb3 => ("ORA E".to_string(), pc + 1),
+        0xb4 => ("ORA H".to_string(), pc + 1),
+        0xb5 => ("ORA L".to_string(), pc + 1),
+        0xb6 => ("ORA M".to_string(), pc + 1),
+        0xb7 => ("ORA A".to_string(), pc + 1),
+        0xb8 => ("CMP B".to_string(), pc + 1),
+        0xb9 => ("CMP C".to_string(), pc + 1),
+        0xba => ("CMP D".to_string(), pc + 1),
+        0xbb => ("CMP E".to_string(), pc + 1),
+        0xbc => ("CMP H".to_string(), pc + 1),
+        0xbd => ("CMP L".to_string(), pc + 1),
+        0xbe => ("CMP M".to_string(), pc + 1),
+        0xbf => ("CMP A".to_string(), pc + 1),
+        0xc0 => ("RNZ".to_string(), pc + 1),
+        0xc1 => ("POP B".to_string(), pc + 1),
+        0xc2 => (
+            format!("JNZ {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xc3 => (
+            format!("JMP {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xc4 => (
+            format!("CNZ {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xc5 => ("PUSH B".to_string(), pc + 1),
+        0xc6 => (format!("ADI {:#04x}", rom[pc + 1]), pc + 2),
+        0xc7 => ("RST 0".to_string(), pc + 1),
+        0xc8 => ("RZ".to_string(), pc + 1),
+        0xc9 => ("RET".to_string(), pc + 1),
+        0xca => (
+            format!("JZ {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xcb => (
+            format!("JMP {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xcc => (
+            format!("CZ {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xcd => (
+            format!("CALL {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xce => (format!("ACI {:#04x}", rom[pc + 1]), pc + 2),
+        0xcf => ("RST 1".to_string(), pc + 1),
+        0xd0 => ("RNC".to_string(), pc + 1),
+        0xd1 => ("POP D".to_string(), pc + 1),
+        0xd2 => (
+            format!("JNC {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xd3 => (format!("OUT {:#04x}", rom[pc + 1]), pc + 2),
+        0xd4 => (
+            format!("CNC {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xd5 => ("PUSH D".to_string(), pc + 1),
+        0xd6 => (format!("SUI {:#04x}", rom[pc + 1]), pc + 2),
+        0xd7 => ("RST 2".to_string(), pc + 1),
+        0xd8 => ("RC".to_string(), pc + 1),
+        0xd9 => ("RET".to_string(), pc + 1),
+        0xda => (
+            format!("JC {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xdb => (format!("IN {:#04x}", rom[pc + 1]), pc + 2),
+        0xdc => (
+            format!("CC {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xdd => (
+            format!("CALL {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xde => (format!("SBI {:#04x}", rom[pc + 1]), pc + 2),
+        0xdf => ("RST 3".to_string(), pc + 1),
+        0xe0 => ("RPO".to_string(), pc + 1),
+        0xe1 => ("POP H".to_string(), pc + 1),
+        0xe2 => (
+            format!("JPO {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xe3 => ("XTHL".to_string(), pc + 1),
+        0xe4 => (
+            format!("CPO {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xe5 => ("PUSH H".to_string(), pc + 1),
+        0xe6 => (format!("ANI {:#04x}", rom[pc + 1]), pc + 2),
+        0xe7 => ("RST 4".to_string(), pc + 1),
+        0xe8 => ("RPE".to_string(), pc + 1),
+        0xe9 => ("PCHL".to_string(), pc + 1),
+        0xea => (
+            format!("JPE {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xeb => ("XCHG".to_string(), pc + 1),
+        0xec => (
+            format!("CPE {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xed => (
+            format!("CALL {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xee => (format!("XRI {:#04x}", rom[pc + 1]), pc + 2),
+        0xef => ("RST 5".to_string(), pc + 1),
+        0xf0 => ("RP".to_string(), pc + 1),
+        0xf1 => ("POP PSW".to_string(), pc + 1),
+        0xf2 => (
+            format!("JP {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xf3 => ("DI".to_string(), pc + 1),
+        0xf4 => (
+            format!("CP {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xf5 => ("PUSH PSW".to_string(), pc + 1),
+        0xf6 => (format!("ORI {:#04x}", rom[pc + 1]), pc + 2),
+        0xf7 => ("RST 6".to_string(), pc + 1),
+        0xf8 => ("RM".to_string(), pc + 1),
+        0xf9 => ("SPHL".to_string(), pc + 1),
+        0xfa => (
+            format!("JM {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xfb => ("EI".to_string(), pc + 1),
+        0xfc => (
+            format!("CM {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xfd => (
+            format!("CALL {:#04x}{:02x}", rom[pc + 2], rom[pc + 1]),
+            pc + 3,
+        ),
+        0xfe => (format!("CPI {:#04x}", rom[pc + 1]), pc + 2),
+        0xff => ("RST 7".to_string(), pc + 1),
+    }
+}
+
+/// just the instruction name of `opcode`, without operands
+pub fn mnemonic(opcode: u8) -> String {
+    let (text, _) = disassembler(0, &[opcode, 0, 0]);
+    text.split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// how many bytes `opcode` and its operands take when executed
+pub fn instruction_length(opcode: u8) -> usize {
+    OPCODES[opcode as usize].length as usize
+}
+
+/// where the jump or call at `pc` goes, if it is one
+pub fn jump_target(pc: usize, rom: &[u8]) -> Option<u16> {
+    match rom.get(pc)? {
+        0xc2 | 0xc3 | 0xca | 0xd2 | 0xda | 0xe2 | 0xea | 0xf2 | 0xfa | 0xc4 | 0xcc | 0xcd
+        | 0xd4 | 0xdc | 0xe4 | 0xec | 0xf4 | 0xfc | 0xcb | 0xdd | 0xed | 0xfd => {
+            Some(u16::from_le_bytes([*rom.get(pc + 1)?, *rom.get(pc + 2)?]))
+        }
+        _ => None,
+    }
+}
+
+/// address and opcode of every undocumented instruction in a linear sweep
+/// of `rom`
+pub fn scan_illegal(rom: &[u8]) -> Vec<(u16, u8)> {
+    let mut found = Vec::new();
+    let mut pc = 0;
+    while pc < rom.len() {
+        if ILLEGAL_OPCODES.contains(&rom[pc]) {
+            found.push((pc as u16, rom[pc]));
+        }
+        pc += instruction_length(rom[pc]);
+    }
+    found
+}
+
+/// the whole of `rom` one instruction per line, with the address and raw
+/// bytes in front: `0x0000: 21 34 12    LXI H, 0x1234`
+pub fn disassemble_to_listing(rom: &[u8]) -> String {
+    Tracer::new().listing(rom)
+}
+
+/// names every jump and call target it sees `L_xxxx` and marks them up in
+/// listings and trace lines
+#[derive(Debug, Default)]
+pub struct Tracer {
+    labels: HashMap<u16, String>,
+}
+
+impl Tracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn labels(&self) -> &HashMap<u16, String> {
+        &self.labels
+    }
+
+    /// records the target of the instruction at `pc`, if it has one
+    pub fn see(&mut self, pc: usize, rom: &[u8]) {
+        if let Some(target) = jump_target(pc, rom) {
+            self.labels
+                .entry(target)
+                .or_insert_with(|| format!("L_{:04x}", target));
+        }
+    }
+
+    /// names from a symbol file, one `0x1234 GameLoop` per line. they win
+    /// over generated labels, lines that don't parse are skipped
+    pub fn load_symbols(&mut self, text: &str) {
+        for line in text.lines() {
+            let mut words = line.split_whitespace();
+            let (Some(addr), Some(name)) = (words.next(), words.next()) else {
+                continue;
+            };
+            let addr = addr.trim_start_matches("0x");
+            if let Ok(addr) = u16::from_str_radix(addr, 16) {
+                self.labels.insert(addr, name.to_string());
+            }
+        }
+    }
+
+    /// labels every target in a linear sweep of `rom`
+    pub fn scan(&mut self, rom: &[u8]) {
+        let mut pc = 0;
+        while pc < rom.len() {
+            self.see(pc, rom);
+            pc = disassembler(pc, rom).1;
+        }
+    }
+
+    /// `disassemble_to_listing` with the labels marked up
+    pub fn listing(&self, rom: &[u8]) -> String {
+        let mut out = String::new();
+        let mut pc = 0;
+        while pc < rom.len() {
+            let (text, next) = disassembler(pc, rom);
+            let bytes: Vec<String> = rom[pc..next].iter().map(|b| format!("{:02x}", b)).collect();
+            out.push_str(&format!(
+                "{:#06x}: {:<8}    {}\n",
+                pc,
+                bytes.join(" "),
+                self.annotate(pc, &text, rom)
+            ));
+            pc = next;
+        }
+        out
+    }
+
+    /// `text` for the instruction at `pc`, prefixed with its own label and
+    /// followed by the label it jumps to
+    pub fn annotate(&self, pc: usize, text: &str, rom: &[u8]) -> String {
+        let mut line = match self.labels.get(&(pc as u16)) {
+            Some(label) => format!("{}: {}", label, text),
+            None => text.to_string(),
+        };
+        if let Some(label) = jump_target(pc, rom).and_then(|target| self.labels.get(&target)) {
+            line.push_str(&format!(" ; {}", label));
+        }
+        line
+    }
+}
+
+impl<B: Bus> Cpu8080<B> {
+    /// the instruction at `addr` and the address of the next one
+    fn disassemble_at(&self, addr: u16) -> (String, u16) {
+        let bytes = [0, 1, 2].map(|i| self.peek(addr.wrapping_add(i)));
+        let (text, _) = disassembler(0, &bytes);
+        let next = addr.wrapping_add(instruction_length(bytes[0]) as u16);
+        (text, next)
+    }
+
+    /// `count` instructions from `pc` on, following their lengths and
+    /// wrapping past 0xffff
+    pub fn list_from(&self, pc: u16, count: usize) -> Vec<(u16, String)> {
+        let mut addr = pc;
+        (0..count)
+            .map(|_| {
+                let (text, next) = self.disassemble_at(addr);
+                let line = (addr, text);
+                addr = next;
+                line
+            })
+            .collect()
+    }
+
+    /// up to `before` instructions leading to pc, the one at pc and `after`
+    /// more, each flagged whether it is the one at pc. going backward is a
+    /// guess: the furthest start that decodes forward onto pc wins
+    pub fn disasm_window(&self, before: usize, after: usize) -> Vec<(u16, String, bool)> {
+        let mut lead = Vec::new();
+        for back in (1..=before * 3).rev() {
+            let mut addr = self.pc.wrapping_sub(back as u16);
+            let mut lines = Vec::new();
+            while addr != self.pc && lines.len() <= back {
+                let (text, next) = self.disassemble_at(addr);
+                lines.push((addr, text, false));
+                addr = next;
+            }
+            if addr == self.pc {
+                lead = lines;
+                break;
+            }
+        }
+        let skip = lead.len().saturating_sub(before);
+        let mut window: Vec<_> = lead.into_iter().skip(skip).collect();
+
+        let mut addr = self.pc;
+        for i in 0..=after {
+            let (text, next) = self.disassemble_at(addr);
+            window.push((addr, text, i == 0));
+            addr = next;
+        }
+        window
+    }
+}
@@ -0,0 +1,204 @@
+use std::ops::BitOr;
+
+use anyhow::{bail, Result};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlayerInput {
+    pub start: bool,
+    pub left: bool,
+    pub right: bool,
+    pub fire: bool,
+}
+
+/// everything the cabinet's controls can tell the game in one poll
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputState {
+    pub coin: bool,
+    pub players: [PlayerInput; 2],
+}
+
+/// a button is down if any of the sources holds it down
+impl BitOr for InputState {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        let player = |a: PlayerInput, b: PlayerInput| PlayerInput {
+            start: a.start || b.start,
+            left: a.left || b.left,
+            right: a.right || b.right,
+            fire: a.fire || b.fire,
+        };
+        Self {
+            coin: self.coin || other.coin,
+            players: [
+                player(self.players[0], other.players[0]),
+                player(self.players[1], other.players[1]),
+            ],
+        }
+    }
+}
+
+/// setters for driving the controls from code, `player` is 0 or 1
+impl InputState {
+    pub fn insert_coin(&mut self) {
+        self.coin = true;
+    }
+
+    pub fn set_coin(&mut self, down: bool) {
+        self.coin = down;
+    }
+
+    pub fn set_start(&mut self, player: usize, down: bool) {
+        self.players[player].start = down;
+    }
+
+    pub fn set_left(&mut self, player: usize, down: bool) {
+        self.players[player].left = down;
+    }
+
+    pub fn set_right(&mut self, player: usize, down: bool) {
+        self.players[player].right = down;
+    }
+
+    pub fn set_fire(&mut self, player: usize, down: bool) {
+        self.players[player].fire = down;
+    }
+
+    /// one bit per button: coin, then start, left, right and fire of each
+    /// player
+    pub fn to_bits(self) -> u16 {
+        let player = |p: PlayerInput| {
+            p.start as u16 | (p.left as u16) << 1 | (p.right as u16) << 2 | (p.fire as u16) << 3
+        };
+        self.coin as u16 | player(self.players[0]) << 1 | player(self.players[1]) << 5
+    }
+
+    pub fn from_bits(bits: u16) -> Self {
+        let player = |bits: u16| PlayerInput {
+            start: bits & 1 != 0,
+            left: bits & 2 != 0,
+            right: bits & 4 != 0,
+            fire: bits & 8 != 0,
+        };
+        Self {
+            coin: bits & 1 != 0,
+            players: [player(bits >> 1), player(bits >> 5)],
+        }
+    }
+}
+
+/// anything that can be polled once per frame for the controls
+pub trait InputSource {
+    fn poll(&mut self) -> InputState;
+}
+
+/// a state set up by hand holds its buttons until it is changed, for tests
+/// and anything else driving the game without a keyboard
+impl InputSource for InputState {
+    fn poll(&mut self) -> InputState {
+        *self
+    }
+}
+
+const RECORDING_MAGIC: &[u8; 4] = b"8inp";
+
+/// the controls as they changed, by frame since the recording started.
+/// replayed against the same rom with a fixed clock it plays back exactly
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    /// the frame each state took effect on, oldest first
+    changes: Vec<(u64, InputState)>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// how many changes it holds
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// notes the controls on `frame`, kept only when they changed
+    pub fn record(&mut self, frame: u64, state: InputState) {
+        if self.changes.last().map(|(_, last)| *last) != Some(state) {
+            self.changes.push((frame, state));
+        }
+    }
+
+    /// the controls in effect on `frame`, nothing pressed before the first
+    /// change
+    pub fn state_at(&self, frame: u64) -> InputState {
+        match self.changes.partition_point(|(start, _)| *start <= frame) {
+            0 => InputState::default(),
+            i => self.changes[i - 1].1,
+        }
+    }
+
+    /// magic, then a little endian u64 frame and u16 of buttons per change
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = RECORDING_MAGIC.to_vec();
+        for (frame, state) in &self.changes {
+            bytes.extend_from_slice(&frame.to_le_bytes());
+            bytes.extend_from_slice(&state.to_bits().to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let Some(body) = bytes.strip_prefix(RECORDING_MAGIC) else {
+            bail!("not an input recording");
+        };
+        if body.len() % 10 != 0 {
+            bail!("input recording is truncated");
+        }
+        let changes = body
+            .chunks(10)
+            .map(|chunk| {
+                let frame = u64::from_le_bytes(chunk[..8].try_into().unwrap());
+                let bits = u16::from_le_bytes([chunk[8], chunk[9]]);
+                (frame, InputState::from_bits(bits))
+            })
+            .collect();
+        Ok(Self { changes })
+    }
+}
+
+/// first two connected pads drive player 1 and 2, select inserts a coin
+#[cfg(feature = "gamepad")]
+pub struct GamepadInput {
+    gilrs: gilrs::Gilrs,
+}
+
+#[cfg(feature = "gamepad")]
+impl GamepadInput {
+    pub fn new() -> anyhow::Result<Self> {
+        let gilrs = gilrs::Gilrs::new().map_err(|err| anyhow::anyhow!(err.to_string()))?;
+        Ok(Self { gilrs })
+    }
+}
+
+#[cfg(feature = "gamepad")]
+impl InputSource for GamepadInput {
+    fn poll(&mut self) -> InputState {
+        use gilrs::Button;
+
+        // drain the queue so the cached pad state is current
+        while self.gilrs.next_event().is_some() {}
+
+        let mut state = InputState::default();
+        for (player, (_, pad)) in state.players.iter_mut().zip(self.gilrs.gamepads()) {
+            player.start = pad.is_pressed(Button::Start);
+            player.left = pad.is_pressed(Button::DPadLeft);
+            player.right = pad.is_pressed(Button::DPadRight);
+            player.fire = pad.is_pressed(Button::South);
+            state.coin |= pad.is_pressed(Button::Select);
+        }
+        state
+    }
+}
@@ -0,0 +1,437 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::bus::{Bus, FlatMemory};
+use crate::config::SPACE_INVADERS;
+use crate::cpu::Cpu8080;
+use crate::input::{InputSource, InputState, Recording};
+use crate::io::Io;
+use crate::scheduler::FrameScheduler;
+use crate::state::STATE_LEN;
+
+pub const DEFAULT_ROM: &str = "./rom/space-invaders/invaders";
+/// where the work ram begins, everything below it is rom
+pub const RAM_START: u16 = 0x2000;
+/// the ram is mirrored from here on
+pub const INVADERS_MIRROR: u16 = 0x4000;
+
+const MACHINE_MAGIC: &[u8; 4] = b"8inv";
+/// kept apart from the cpu state's version, which rides along inside
+const MACHINE_VERSION: u8 = 1;
+/// magic, version, the cpu state, shift register, shift offset, ports 3
+/// and 5, controls, scheduler cycles and frames, then the pending
+/// interrupt count
+const MACHINE_FIXED_LEN: usize = 5 + STATE_LEN + 2 + 1 + 2 + 2 + 4 + 8 + 1;
+
+/// the original board ships the rom as four 2 KiB chips instead of one blob
+pub const INVADERS_FILES: [(&str, u16); 4] = [
+    ("./rom/space-invaders/invaders.h", 0x0000),
+    ("./rom/space-invaders/invaders.g", 0x0800),
+    ("./rom/space-invaders/invaders.f", 0x1000),
+    ("./rom/space-invaders/invaders.e", 0x1800),
+];
+
+/// loads the game from `path`. the default rom falls back to the four chip
+/// files when only those are on disk. returns how many bytes of rom it put
+/// in memory
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_rom<B: Bus>(cpu: &mut Cpu8080<B>, path: &str) -> Result<usize> {
+    if path == DEFAULT_ROM && !Path::new(DEFAULT_ROM).exists() {
+        cpu.load_files(&INVADERS_FILES)?;
+        return Ok(0x2000);
+    }
+    cpu.load_file(path)
+}
+
+/// the board's address decoding: the rom below `RAM_START` ignores writes
+/// and the 8 KiB of ram shows up again from `INVADERS_MIRROR` upward
+#[derive(Debug, Clone)]
+pub struct InvadersBus {
+    memory: FlatMemory,
+    /// collect the vram addresses written to, see `take_dirty_vram`
+    pub track_vram: bool,
+    dirty_vram: Vec<u16>,
+}
+
+impl InvadersBus {
+    /// the chips holding `memory`, rom already in place
+    pub fn new(memory: FlatMemory) -> Self {
+        Self {
+            memory,
+            track_vram: false,
+            dirty_vram: Vec::new(),
+        }
+    }
+
+    /// folds an address at or above `INVADERS_MIRROR` back into the ram
+    fn mirrored(addr: u16) -> u16 {
+        match addr < INVADERS_MIRROR {
+            true => addr,
+            false => RAM_START + (addr - INVADERS_MIRROR) % (INVADERS_MIRROR - RAM_START),
+        }
+    }
+
+    /// every vram address written since the last call, sorted and without
+    /// repeats. only filled while `track_vram` is on
+    pub fn take_dirty_vram(&mut self) -> Vec<u16> {
+        let mut dirty = std::mem::take(&mut self.dirty_vram);
+        dirty.sort_unstable();
+        dirty.dedup();
+        dirty
+    }
+}
+
+impl Default for InvadersBus {
+    fn default() -> Self {
+        Self::new(FlatMemory::new())
+    }
+}
+
+impl Bus for InvadersBus {
+    #[inline]
+    fn read(&self, addr: u16) -> u8 {
+        self.memory.read(Self::mirrored(addr))
+    }
+
+    #[inline]
+    fn write(&mut self, addr: u16, value: u8) {
+        if Self::mirrored(addr) >= RAM_START {
+            self.poke(addr, value);
+        }
+    }
+
+    fn poke(&mut self, addr: u16, value: u8) {
+        let addr = Self::mirrored(addr);
+        if self.track_vram && SPACE_INVADERS.vram().contains(&(addr as usize)) {
+            self.dirty_vram.push(addr);
+        }
+        self.memory.write(addr, value);
+    }
+}
+
+/// the whole board around the cpu: address decoding, the io board with its
+/// shift register and the video timing that raises the two interrupts.
+/// front-ends drive this instead of wiring the pieces up themselves
+#[derive(Debug)]
+pub struct SpaceInvaders {
+    pub cpu: Cpu8080<InvadersBus>,
+    pub io: InvadersIo,
+    pub scheduler: FrameScheduler,
+}
+
+impl SpaceInvaders {
+    /// puts `cpu`, rom already loaded, on the board. its memory moves onto
+    /// an `InvadersBus`, which does the mirroring and keeps the rom intact
+    pub fn new(cpu: Cpu8080) -> Self {
+        let mut cpu = cpu.map_bus(InvadersBus::new);
+        // a line per instruction adds up to millions a minute, nothing
+        // running the game reads back further than the last step
+        cpu.keep_history = false;
+        Self {
+            cpu,
+            io: InvadersIo::new(),
+            scheduler: FrameScheduler::with_config(&SPACE_INVADERS),
+        }
+    }
+
+    /// one instruction, raising an interrupt when the beam gets to one.
+    /// returns the clock cycles it took
+    pub fn step(&mut self) -> u8 {
+        let cycles = self.cpu.step_io(&mut self.io);
+        if let Some(rst) = self.scheduler.tick(cycles as u32) {
+            self.cpu.request_interrupt(rst);
+        }
+        cycles
+    }
+
+    /// runs to the next vblank
+    pub fn run_frame(&mut self) {
+        self.scheduler.run_frame(&mut self.cpu, &mut self.io);
+    }
+
+    /// emulated frames completed so far
+    pub fn frames(&self) -> u64 {
+        self.scheduler.frames()
+    }
+
+    /// `Cpu8080::save_state` plus what the board holds: the shift register,
+    /// the latched ports and controls, where the beam is and the interrupts
+    /// not yet taken. enough to carry on mid-frame as if never stopped.
+    /// settings like the dip switches and recordings are left out
+    pub fn save_state(&self) -> Vec<u8> {
+        let io = &self.io;
+        let mut state = Vec::with_capacity(MACHINE_FIXED_LEN);
+        state.extend_from_slice(MACHINE_MAGIC);
+        state.push(MACHINE_VERSION);
+        state.extend(self.cpu.save_state());
+        state.extend_from_slice(&io.shift.to_le_bytes());
+        state.extend_from_slice(&[io.shift_offset, io.port3, io.port5]);
+        state.extend_from_slice(&io.input.to_bits().to_le_bytes());
+        state.extend_from_slice(&self.scheduler.cycles.to_le_bytes());
+        state.extend_from_slice(&self.scheduler.frames.to_le_bytes());
+        state.push(self.cpu.pending_interrupts.len() as u8);
+        state.extend(&self.cpu.pending_interrupts);
+        state
+    }
+
+    pub fn load_state(&mut self, state: &[u8]) -> Result<()> {
+        if state.len() < MACHINE_FIXED_LEN || &state[..4] != MACHINE_MAGIC {
+            bail!("not a machine save state");
+        }
+        if state[4] != MACHINE_VERSION {
+            bail!("machine save state version {} is not supported", state[4]);
+        }
+        let pending = &state[MACHINE_FIXED_LEN..];
+        if pending.len() != state[MACHINE_FIXED_LEN - 1] as usize {
+            bail!("machine save state is truncated");
+        }
+        if let Some(rst) = pending.iter().find(|&&rst| rst > 7) {
+            bail!("machine save state has a pending RST {}", rst);
+        }
+        let (cpu, board) = state[5..MACHINE_FIXED_LEN - 1].split_at(STATE_LEN);
+        self.cpu.load_state(cpu)?;
+        self.io.shift = u16::from_le_bytes([board[0], board[1]]);
+        [self.io.shift_offset, self.io.port3, self.io.port5] = [board[2], board[3], board[4]];
+        self.io.input = InputState::from_bits(u16::from_le_bytes([board[5], board[6]]));
+        self.scheduler.cycles = u32::from_le_bytes(board[7..11].try_into()?);
+        self.scheduler.frames = u64::from_le_bytes(board[11..19].try_into()?);
+        self.cpu.pending_interrupts = pending.iter().copied().collect();
+        Ok(())
+    }
+}
+
+/// one of the discrete sound circuits, triggered by a bit on port 3 or 5
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundEvent {
+    /// loops for as long as the bit stays set
+    Ufo,
+    Shot,
+    PlayerDeath,
+    InvaderDeath,
+    Fleet1,
+    Fleet2,
+    Fleet3,
+    Fleet4,
+    UfoHit,
+}
+
+impl SoundEvent {
+    pub const ALL: [SoundEvent; 9] = [
+        SoundEvent::Ufo,
+        SoundEvent::Shot,
+        SoundEvent::PlayerDeath,
+        SoundEvent::InvaderDeath,
+        SoundEvent::Fleet1,
+        SoundEvent::Fleet2,
+        SoundEvent::Fleet3,
+        SoundEvent::Fleet4,
+        SoundEvent::UfoHit,
+    ];
+
+    /// port and bit that drive this sound
+    fn line(self) -> (u8, u8) {
+        match self {
+            SoundEvent::Ufo => (3, 0),
+            SoundEvent::Shot => (3, 1),
+            SoundEvent::PlayerDeath => (3, 2),
+            SoundEvent::InvaderDeath => (3, 3),
+            SoundEvent::Fleet1 => (5, 0),
+            SoundEvent::Fleet2 => (5, 1),
+            SoundEvent::Fleet3 => (5, 2),
+            SoundEvent::Fleet4 => (5, 3),
+            SoundEvent::UfoHit => (5, 4),
+        }
+    }
+}
+
+/// the switches on the board the operator sets, read through port 2
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DipSwitches {
+    /// ships per game, 3 to 6
+    pub lives: u8,
+    /// score that earns the extra ship, 1000 or 1500
+    pub bonus_life: u16,
+}
+
+impl Default for DipSwitches {
+    fn default() -> Self {
+        Self {
+            lives: 3,
+            bonus_life: 1500,
+        }
+    }
+}
+
+impl DipSwitches {
+    /// bits 0-1 count the lives above 3, bit 3 moves the bonus down to 1000
+    fn bits(self) -> u8 {
+        (self.lives.clamp(3, 6) - 3) | ((self.bonus_life <= 1000) as u8) << 3
+    }
+}
+
+/// space invaders io board: the input ports and the hardware shift register
+/// the game uses to move sprites by a bit offset
+#[derive(Debug, Default)]
+pub struct InvadersIo {
+    pub input: InputState,
+    shift: u16,
+    shift_offset: u8,
+    port3: u8,
+    port5: u8,
+    /// how many times the game has kicked the watchdog on port 6. nothing
+    /// resets the machine when it stops, this is only for whoever watches
+    pub watchdog: u64,
+    /// one line for the first IN and the first OUT on each port the board
+    /// doesn't wire up
+    unhandled: Vec<String>,
+    /// sounds whose bit went from 0 to 1 since the last `take_sounds`, one
+    /// bit per entry of `SoundEvent::ALL` so nothing piles up if no one reads
+    triggered: u16,
+    /// a cocktail cabinet, where the players sit facing each other and the
+    /// screen flips for player 2
+    pub cocktail: bool,
+    pub dips: DipSwitches,
+    /// `update`s since the recording or replay started
+    frame: u64,
+    recording: Option<Recording>,
+    /// while set, `update` takes the controls from here instead
+    replay: Option<Recording>,
+}
+
+impl InvadersIo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// latch the controls for the coming frame
+    pub fn update(&mut self, source: &mut dyn InputSource) {
+        self.input = match &self.replay {
+            Some(replay) => replay.state_at(self.frame),
+            None => source.poll(),
+        };
+        if let Some(recording) = &mut self.recording {
+            recording.record(self.frame, self.input);
+        }
+        self.frame += 1;
+    }
+
+    /// records the controls of every `update` from here on
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording::new());
+        self.frame = 0;
+    }
+
+    pub fn replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    pub fn recording(&self) -> Option<&Recording> {
+        self.recording.as_ref()
+    }
+
+    /// the recording so far, see `Recording::to_bytes`
+    pub fn save_recording(&self) -> Vec<u8> {
+        self.recording.clone().unwrap_or_default().to_bytes()
+    }
+
+    /// plays a saved recording back from the next `update` on, live input
+    /// is ignored while it plays
+    pub fn load_recording(&mut self, bytes: &[u8]) -> Result<()> {
+        self.replay = Some(Recording::from_bytes(bytes)?);
+        self.frame = 0;
+        Ok(())
+    }
+
+    pub fn unhandled(&self) -> &[String] {
+        &self.unhandled
+    }
+
+    fn log_unhandled(&mut self, line: String) {
+        if !self.unhandled.contains(&line) {
+            self.unhandled.push(line);
+        }
+    }
+
+    /// drains the sounds triggered since the last call
+    pub fn take_sounds(&mut self) -> Vec<SoundEvent> {
+        let triggered = std::mem::take(&mut self.triggered);
+        SoundEvent::ALL
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| triggered & (1 << i) != 0)
+            .map(|(_, sound)| sound)
+            .collect()
+    }
+
+    /// whether the bit behind `sound` is currently set
+    pub fn sound_on(&self, sound: SoundEvent) -> bool {
+        let (port, bit) = sound.line();
+        let value = match port {
+            3 => self.port3,
+            _ => self.port5,
+        };
+        value & (1 << bit) != 0
+    }
+
+    /// the game sets bit 5 of port 5 on player 2's turn, only a cocktail
+    /// cabinet wires it to the monitor
+    pub fn flip_screen(&self) -> bool {
+        self.cocktail && self.port5 & 0x20 != 0
+    }
+
+    fn sound_port(&mut self, port: u8, value: u8) {
+        let previous = match port {
+            3 => std::mem::replace(&mut self.port3, value),
+            _ => std::mem::replace(&mut self.port5, value),
+        };
+        let rising = value & !previous;
+        for (i, sound) in SoundEvent::ALL.into_iter().enumerate() {
+            let (line_port, bit) = sound.line();
+            if line_port == port && rising & (1 << bit) != 0 {
+                self.triggered |= 1 << i;
+            }
+        }
+    }
+
+    fn port1(&self) -> u8 {
+        let [p1, p2] = self.input.players;
+        // bit 3 is tied high on the board
+        self.input.coin as u8
+            | (p2.start as u8) << 1
+            | (p1.start as u8) << 2
+            | 1 << 3
+            | (p1.fire as u8) << 4
+            | (p1.left as u8) << 5
+            | (p1.right as u8) << 6
+    }
+
+    fn port2(&self) -> u8 {
+        let p2 = self.input.players[1];
+        self.dips.bits() | (p2.fire as u8) << 4 | (p2.left as u8) << 5 | (p2.right as u8) << 6
+    }
+}
+
+impl Io for InvadersIo {
+    fn input(&mut self, port: u8) -> u8 {
+        match port {
+            1 => self.port1(),
+            2 => self.port2(),
+            3 => (self.shift >> (8 - self.shift_offset)) as u8,
+            _ => {
+                self.log_unhandled(format!("IN {:#04x}", port));
+                0
+            }
+        }
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        match port {
+            2 => self.shift_offset = value & 0x07,
+            3 | 5 => self.sound_port(port, value),
+            4 => self.shift = (value as u16) << 8 | self.shift >> 8,
+            6 => self.watchdog += 1,
+            _ => self.log_unhandled(format!("OUT {:#04x}", port)),
+        }
+    }
+}
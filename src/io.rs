@@ -0,0 +1,32 @@
+/// whatever sits behind the IN and OUT instructions
+pub trait Io {
+    fn input(&mut self, port: u8) -> u8;
+    fn output(&mut self, port: u8, value: u8);
+}
+
+/// nothing attached: reads are 0 and writes are dropped
+#[derive(Debug, Default)]
+pub struct NullIo;
+
+impl Io for NullIo {
+    fn input(&mut self, _port: u8) -> u8 {
+        0
+    }
+
+    fn output(&mut self, _port: u8, _value: u8) {}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IoDirection {
+    In,
+    Out,
+}
+
+/// one IN or OUT, `cycle` is the cpu's cycle count when it started
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IoEvent {
+    pub cycle: u64,
+    pub direction: IoDirection,
+    pub port: u8,
+    pub value: u8,
+}
@@ -0,0 +1,21 @@
+#![allow(unused)]
+
+pub mod access;
+pub mod assembler;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod bus;
+pub mod clock;
+pub mod config;
+pub mod cpm;
+pub mod cpu;
+pub mod disassembler;
+pub mod input;
+pub mod invaders;
+pub mod io;
+pub mod scheduler;
+pub mod state;
+pub mod trace;
+pub mod video;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,119 @@
+use std::collections::VecDeque;
+
+use crate::bus::Bus;
+use crate::config::{MachineConfig, SPACE_INVADERS};
+use crate::cpu::Cpu8080;
+use crate::io::Io;
+
+/// tracks where the beam is inside the frame so the two video interrupts
+/// land on time instead of once per front-end frame
+#[derive(Debug)]
+pub struct FrameScheduler {
+    /// into the current frame, saved with the machine state
+    pub(crate) cycles: u32,
+    frame: u32,
+    /// vblanks raised so far
+    pub(crate) frames: u64,
+    /// the beam reaches the interrupt scanline here and raises RST 1
+    mid_frame: u32,
+}
+
+impl FrameScheduler {
+    pub fn new() -> Self {
+        Self::with_config(&SPACE_INVADERS)
+    }
+
+    pub fn with_config(config: &MachineConfig) -> Self {
+        Self {
+            cycles: 0,
+            frame: config.cycles_per_frame(),
+            frames: 0,
+            mid_frame: config.interrupt_cycle(),
+        }
+    }
+
+    /// emulated frames completed so far
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// counts `cycles` more and returns the rst to raise when a mark is
+    /// crossed: 1 at mid-screen, 2 at vblank
+    pub fn tick(&mut self, cycles: u32) -> Option<u8> {
+        let before = self.cycles;
+        self.cycles += cycles;
+        if before < self.mid_frame && self.cycles >= self.mid_frame {
+            return Some(1);
+        }
+        if self.cycles >= self.frame {
+            self.cycles -= self.frame;
+            self.frames += 1;
+            return Some(2);
+        }
+        None
+    }
+
+    /// runs until the end of the current frame, raising both interrupts
+    /// along the way. does nothing while the cpu is paused
+    pub fn run_frame<B: Bus>(&mut self, cpu: &mut Cpu8080<B>, io: &mut dyn Io) {
+        while !cpu.paused {
+            let cycles = cpu.step_io(io);
+            match self.tick(cycles as u32) {
+                Some(2) => {
+                    cpu.request_interrupt(2);
+                    break;
+                }
+                Some(rst) => cpu.request_interrupt(rst),
+                None => {}
+            }
+        }
+    }
+}
+
+impl Default for FrameScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// frames per second averaged over the last few frames
+#[derive(Debug)]
+pub struct FpsCounter {
+    /// timestamps in seconds, oldest first
+    stamps: VecDeque<f64>,
+    window: usize,
+}
+
+impl FpsCounter {
+    /// averages over the last `window` frames
+    pub fn new(window: usize) -> Self {
+        Self {
+            stamps: VecDeque::with_capacity(window + 1),
+            window: window.max(1),
+        }
+    }
+
+    /// a frame finished at `time` seconds
+    pub fn tick(&mut self, time: f64) {
+        self.stamps.push_back(time);
+        if self.stamps.len() > self.window + 1 {
+            self.stamps.pop_front();
+        }
+    }
+
+    /// 0 until two frames have been seen
+    pub fn fps(&self) -> f64 {
+        match (self.stamps.front(), self.stamps.back()) {
+            (Some(first), Some(last)) if last > first => {
+                (self.stamps.len() - 1) as f64 / (last - first)
+            }
+            _ => 0.,
+        }
+    }
+}
+
+impl Default for FpsCounter {
+    fn default() -> Self {
+        Self::new(60)
+    }
+}
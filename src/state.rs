@@ -0,0 +1,323 @@
+use std::collections::VecDeque;
+
+use anyhow::{bail, Result};
+
+use crate::bus::Bus;
+use crate::cpu::Cpu8080;
+use crate::io::Io;
+
+const MAGIC: &[u8; 4] = b"8080";
+const VERSION: u8 = 2;
+/// magic, version, a b c d e h l, psw flags, sp, pc, interrupt, halt and
+/// an EI still waiting on the next instruction
+const HEADER_LEN: usize = 20;
+pub const STATE_LEN: usize = HEADER_LEN + 0x10000;
+
+/// where each register sits in the header, with its width in bytes
+const REGISTERS: [(&str, usize, usize); 10] = [
+    ("a", 5, 1),
+    ("b", 6, 1),
+    ("c", 7, 1),
+    ("d", 8, 1),
+    ("e", 9, 1),
+    ("h", 10, 1),
+    ("l", 11, 1),
+    ("sp", 13, 2),
+    ("pc", 15, 2),
+    ("interrupt", 17, 1),
+];
+/// bits of the psw byte at offset 12, see `Cpu8080::flags_byte`
+const FLAGS: [(&str, u8); 5] = [("s", 7), ("z", 6), ("ac", 4), ("p", 2), ("cy", 0)];
+
+impl<B: Bus> Cpu8080<B> {
+    /// registers, flags and the whole 64 KiB address space as one blob.
+    /// debugging state like history and breakpoints is left out
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = Vec::with_capacity(STATE_LEN);
+        state.extend_from_slice(MAGIC);
+        state.push(VERSION);
+        state.extend_from_slice(&[self.a, self.b, self.c, self.d, self.e, self.h, self.l]);
+        state.push(self.flags_byte());
+        state.extend_from_slice(&self.sp.to_le_bytes());
+        state.extend_from_slice(&self.pc.to_le_bytes());
+        state.push(self.interrupt as u8);
+        state.push(self.halt as u8);
+        state.push(self.ei_pending as u8);
+        state.extend((0..=0xffff).map(|addr| self.memory.read(addr)));
+        state
+    }
+
+    pub fn load_state(&mut self, state: &[u8]) -> Result<()> {
+        if state.len() < 5 || &state[..4] != MAGIC {
+            bail!("not a save state");
+        }
+        if state[4] != VERSION {
+            bail!("save state version {} is not supported", state[4]);
+        }
+        if state.len() != STATE_LEN {
+            bail!("save state is truncated");
+        }
+        [self.a, self.b, self.c, self.d, self.e, self.h, self.l] = [
+            state[5], state[6], state[7], state[8], state[9], state[10], state[11],
+        ];
+        self.set_flags_byte(state[12]);
+        self.sp = u16::from_le_bytes([state[13], state[14]]);
+        self.pc = u16::from_le_bytes([state[15], state[16]]);
+        self.interrupt = state[17] != 0;
+        self.halt = state[18] != 0;
+        self.ei_pending = state[19] != 0;
+        for (addr, &byte) in (0..=0xffff).zip(&state[HEADER_LEN..]) {
+            self.memory.poke(addr, byte);
+        }
+        Ok(())
+    }
+}
+
+/// a save state plus the bookkeeping outside it that a replay from there
+/// would otherwise run on top of
+#[derive(Debug, Clone)]
+struct Snapshot {
+    /// the step count it was taken at
+    step: u64,
+    state: Vec<u8>,
+    call_stack: Vec<u16>,
+    cycles: u64,
+    pending_interrupts: VecDeque<u8>,
+}
+
+/// save states taken every `interval` instructions, the last `depth` kept,
+/// so `step_back` has somewhere to start from. each one is `STATE_LEN` bytes
+#[derive(Debug, Clone)]
+pub struct Rewind {
+    interval: u64,
+    depth: usize,
+    /// oldest first
+    snapshots: VecDeque<Snapshot>,
+    /// what each IN read since the oldest snapshot, with the step it ran
+    /// in, so a replay reads the same values without the board
+    inputs: VecDeque<(u64, u8)>,
+    /// the RSTs requested since the oldest snapshot, with the step they
+    /// came after
+    requests: VecDeque<(u64, u8)>,
+}
+
+/// hands a replay the INs recorded the first time through, OUTs already
+/// reached the board then and are dropped
+struct Replay(VecDeque<u8>);
+
+impl Io for Replay {
+    fn input(&mut self, _port: u8) -> u8 {
+        self.0.pop_front().unwrap_or(0)
+    }
+
+    fn output(&mut self, _port: u8, _value: u8) {}
+}
+
+impl Rewind {
+    pub fn new(interval: u64, depth: usize) -> Self {
+        Self {
+            interval: interval.max(1),
+            depth: depth.max(1),
+            snapshots: VecDeque::new(),
+            inputs: VecDeque::new(),
+            requests: VecDeque::new(),
+        }
+    }
+}
+
+impl<B: Bus> Cpu8080<B> {
+    /// called before each step, takes a snapshot when one is due
+    pub(crate) fn rewind_snapshot(&mut self) {
+        let due = match &self.rewind {
+            Some(rewind) => {
+                self.steps.is_multiple_of(rewind.interval)
+                    && rewind.snapshots.back().map(|snapshot| snapshot.step) != Some(self.steps)
+            }
+            None => false,
+        };
+        if !due {
+            return;
+        }
+        let snapshot = Snapshot {
+            step: self.steps,
+            state: self.save_state(),
+            call_stack: self.call_stack.clone(),
+            cycles: self.cycles,
+            pending_interrupts: self.pending_interrupts.clone(),
+        };
+        if let Some(rewind) = &mut self.rewind {
+            if rewind.snapshots.len() == rewind.depth {
+                rewind.snapshots.pop_front();
+            }
+            rewind.snapshots.push_back(snapshot);
+            // nothing replays from before the oldest snapshot
+            if let Some(oldest) = rewind.snapshots.front().map(|snapshot| snapshot.step) {
+                rewind.inputs.retain(|&(step, _)| step > oldest);
+                rewind.requests.retain(|&(step, _)| step > oldest);
+            }
+        }
+    }
+
+    /// called by IN with what it read, kept for `step_back`'s replay
+    pub(crate) fn rewind_input(&mut self, value: u8) {
+        if let Some(rewind) = &mut self.rewind {
+            rewind.inputs.push_back((self.steps, value));
+        }
+    }
+
+    /// called by `request_interrupt`, kept for `step_back`'s replay
+    pub(crate) fn rewind_request(&mut self, rst: u8) {
+        if let Some(rewind) = &mut self.rewind {
+            rewind.requests.push_back((self.steps, rst));
+        }
+    }
+
+    /// undoes the last step: restores the newest snapshot before it and
+    /// runs forward again to just short of it. INs read what they read the
+    /// first time, interrupts are requested where they were, and the replay stays out of history, the trace sink and
+    /// `on_step`. the io board itself isn't rolled back. false when
+    /// `rewind` is off or has nothing that old
+    pub fn step_back(&mut self) -> bool {
+        let Some(target) = self.steps.checked_sub(1) else {
+            return false;
+        };
+        let Some(mut rewind) = self.rewind.take() else {
+            return false;
+        };
+        while rewind
+            .snapshots
+            .back()
+            .is_some_and(|snapshot| snapshot.step > target)
+        {
+            rewind.snapshots.pop_back();
+        }
+        let Some(snapshot) = rewind.snapshots.back() else {
+            self.rewind = Some(rewind);
+            return false;
+        };
+        if self.load_state(&snapshot.state).is_err() {
+            self.rewind = Some(rewind);
+            return false;
+        }
+        self.steps = snapshot.step;
+        self.call_stack.clone_from(&snapshot.call_stack);
+        self.cycles = snapshot.cycles;
+        self.pending_interrupts
+            .clone_from(&snapshot.pending_interrupts);
+        let start = snapshot.step;
+        let mut replay = Replay(
+            rewind
+                .inputs
+                .iter()
+                .filter(|&&(step, _)| step > start && step <= target)
+                .map(|&(_, value)| value)
+                .collect(),
+        );
+        let mut requests: VecDeque<(u64, u8)> = rewind
+            .requests
+            .iter()
+            .copied()
+            .filter(|&(step, _)| step > start && step <= target)
+            .collect();
+        rewind.inputs.retain(|&(step, _)| step <= target);
+        rewind.requests.retain(|&(step, _)| step <= target);
+
+        // with `rewind` taken out the replay doesn't record any of it again.
+        // a paused step_io doesn't count a step, it would never end
+        let paused = std::mem::replace(&mut self.paused, false);
+        let sink = self.trace_sink.take();
+        let hook = self.on_step.take();
+        let changes = std::mem::replace(&mut self.trace_changes, false);
+        let history = std::mem::take(&mut self.history);
+        loop {
+            while let Some((_, rst)) = requests.pop_front_if(|(step, _)| *step == self.steps) {
+                self.request_interrupt(rst);
+            }
+            if self.steps >= target {
+                break;
+            }
+            self.step_io(&mut replay);
+        }
+        self.history = history;
+        self.paused = paused;
+        self.trace_sink = sink;
+        self.on_step = hook;
+        self.trace_changes = changes;
+        self.rewind = Some(rewind);
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateDiff {
+    /// one of the two isn't a save state of this format and version
+    Format,
+    Register {
+        name: &'static str,
+        a: u16,
+        b: u16,
+    },
+    Flag {
+        name: &'static str,
+        a: bool,
+        b: bool,
+    },
+    /// inclusive run of addresses that differ
+    Memory {
+        start: u16,
+        end: u16,
+    },
+}
+
+/// what changed between two `save_state` blobs, memory coalesced into spans
+pub fn diff_state(a: &[u8], b: &[u8]) -> Vec<StateDiff> {
+    let valid =
+        |state: &[u8]| state.len() == STATE_LEN && &state[..4] == MAGIC && state[4] == VERSION;
+    if !valid(a) || !valid(b) {
+        return vec![StateDiff::Format];
+    }
+
+    let mut diffs = Vec::new();
+    let value = |state: &[u8], offset: usize, width: usize| match width {
+        2 => u16::from_le_bytes([state[offset], state[offset + 1]]),
+        _ => state[offset] as u16,
+    };
+    for (name, offset, width) in REGISTERS {
+        let (x, y) = (value(a, offset, width), value(b, offset, width));
+        if x != y {
+            diffs.push(StateDiff::Register { name, a: x, b: y });
+        }
+    }
+    for (name, bit) in FLAGS {
+        let (x, y) = (a[12] & (1 << bit) != 0, b[12] & (1 << bit) != 0);
+        if x != y {
+            diffs.push(StateDiff::Flag { name, a: x, b: y });
+        }
+    }
+    for (name, offset) in [("halt", 18), ("ei_pending", 19)] {
+        if a[offset] != b[offset] {
+            diffs.push(StateDiff::Flag {
+                name,
+                a: a[offset] != 0,
+                b: b[offset] != 0,
+            });
+        }
+    }
+
+    let mut start = None;
+    for addr in 0..=0x10000usize {
+        let differs = addr < 0x10000 && a[HEADER_LEN + addr] != b[HEADER_LEN + addr];
+        match (differs, start) {
+            (true, None) => start = Some(addr as u16),
+            (false, Some(first)) => {
+                diffs.push(StateDiff::Memory {
+                    start: first,
+                    end: (addr - 1) as u16,
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    diffs
+}
@@ -0,0 +1,115 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Error, Result};
+
+use crate::bus::Bus;
+use crate::cpu::Cpu8080;
+
+/// the register file at one point of a run, the flags packed like PUSH PSW
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceLine {
+    pub pc: u16,
+    pub sp: u16,
+    pub af: u16,
+    pub bc: u16,
+    pub de: u16,
+    pub hl: u16,
+}
+
+impl TraceLine {
+    pub fn capture<B: Bus>(cpu: &Cpu8080<B>) -> Self {
+        Self {
+            pc: cpu.pc,
+            sp: cpu.sp,
+            af: (cpu.a as u16) << 8 | cpu.flags_byte() as u16,
+            bc: cpu.bc(),
+            de: cpu.de(),
+            hl: cpu.hl(),
+        }
+    }
+
+    /// what differs in `after`, like `A:0x00->0x01 Z:1->0`. pc is left out,
+    /// every instruction moves it
+    pub fn changes(&self, after: &TraceLine) -> String {
+        let registers = |line: &TraceLine| {
+            [
+                ("A", line.af >> 8),
+                ("B", line.bc >> 8),
+                ("C", line.bc & 0xff),
+                ("D", line.de >> 8),
+                ("E", line.de & 0xff),
+                ("H", line.hl >> 8),
+                ("L", line.hl & 0xff),
+            ]
+        };
+        let flags = |line: &TraceLine| {
+            [
+                ("S", 0x80),
+                ("Z", 0x40),
+                ("AC", 0x10),
+                ("P", 0x04),
+                ("CY", 0x01),
+            ]
+            .map(|(name, bit)| (name, (line.af & bit != 0) as u8))
+        };
+
+        let mut changes = Vec::new();
+        for ((name, old), (_, new)) in registers(self).into_iter().zip(registers(after)) {
+            if old != new {
+                changes.push(format!("{}:{:#04x}->{:#04x}", name, old, new));
+            }
+        }
+        if self.sp != after.sp {
+            changes.push(format!("SP:{:#06x}->{:#06x}", self.sp, after.sp));
+        }
+        for ((name, old), (_, new)) in flags(self).into_iter().zip(flags(after)) {
+            if old != new {
+                changes.push(format!("{}:{}->{}", name, old, new));
+            }
+        }
+        changes.join(" ")
+    }
+}
+
+/// reads the `PC: 0100, AF: 0002, BC: 0000, ...` lines other emulators log,
+/// anything after the known fields is ignored
+impl FromStr for TraceLine {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let field = |name: &str| -> Result<u16> {
+            let start = line
+                .find(&format!("{}: ", name))
+                .ok_or_else(|| anyhow!("no {} in {:?}", name, line))?
+                + name.len()
+                + 2;
+            let hex: String = line[start..]
+                .chars()
+                .take_while(|c| c.is_ascii_hexdigit())
+                .collect();
+            u16::from_str_radix(&hex, 16).with_context(|| format!("bad {} in {:?}", name, line))
+        };
+        Ok(Self {
+            pc: field("PC")?,
+            sp: field("SP")?,
+            af: field("AF")?,
+            bc: field("BC")?,
+            de: field("DE")?,
+            hl: field("HL")?,
+        })
+    }
+}
+
+impl<B: Bus> Cpu8080<B> {
+    /// steps once per reference line, up to `max_steps`, and returns the
+    /// index of the first line the state after the step doesn't match
+    pub fn compare_trace(&mut self, reference: &[TraceLine], max_steps: usize) -> Option<usize> {
+        for (i, expected) in reference.iter().take(max_steps).enumerate() {
+            self.step();
+            if TraceLine::capture(self) != *expected {
+                return Some(i);
+            }
+        }
+        None
+    }
+}
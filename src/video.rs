@@ -0,0 +1,97 @@
+use crate::config::{MachineConfig, SPACE_INVADERS};
+use crate::invaders::SpaceInvaders;
+
+/// screen size after the cabinet's rotation, the raw vram is 256x224
+pub const SCREEN_WIDTH: usize = SPACE_INVADERS.screen_width;
+pub const SCREEN_HEIGHT: usize = SPACE_INVADERS.screen_height;
+
+/// for space invaders, the vram starts from 0x2400 until 0x3fff
+pub const VRAM_START: usize = SPACE_INVADERS.vram_base as usize;
+pub const VRAM_END: usize = VRAM_START + SPACE_INVADERS.vram_len as usize;
+
+const WHITE: [u8; 3] = [0xff, 0xff, 0xff];
+const RED: [u8; 3] = [0xff, 0x20, 0x20];
+const GREEN: [u8; 3] = [0x20, 0xff, 0x20];
+
+/// the cabinet is monochrome, color comes from strips of cellophane glued
+/// to the screen. coordinates are in the rotated screen space
+pub fn overlay_color(x: usize, y: usize) -> [u8; 3] {
+    match y {
+        32..=63 => RED,
+        184..=239 => GREEN,
+        240.. if (16..134).contains(&x) => GREEN,
+        _ => WHITE,
+    }
+}
+
+/// where bit `bit` of vram byte `i` lands on screen. the vram is stored
+/// rotated -90 deg, `flip` turns the result another 180 deg for player 2
+/// on a cocktail cabinet
+pub fn pixel_position(config: &MachineConfig, i: usize, bit: usize, flip: bool) -> (usize, usize) {
+    let (width, height) = (config.screen_width, config.screen_height);
+    let x = (i * 8 + bit) / height;
+    let y = height - 1 - (i * 8 + bit) % height;
+    match flip {
+        true => (width - 1 - x, height - 1 - y),
+        false => (x, y),
+    }
+}
+
+/// largest whole scale the `SCREEN_WIDTH`x`SCREEN_HEIGHT` screen fits into
+/// a `width`x`height` window at, and the x and y offsets that center it.
+/// never below 1, a window too small for the screen crops it instead
+pub fn letterbox(width: u32, height: u32) -> (u32, u32, u32) {
+    let (screen_width, screen_height) = (SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32);
+    let scale = (width / screen_width).min(height / screen_height).max(1);
+    let x = width.saturating_sub(screen_width * scale) / 2;
+    let y = height.saturating_sub(screen_height * scale) / 2;
+    (scale, x, y)
+}
+
+impl SpaceInvaders {
+    /// rgba pixels of the screen, white for set bits and black for clear
+    /// ones. a cocktail cabinet turns it around on player 2's turn
+    pub fn framebuffer(&self) -> Vec<u8> {
+        self.render(false)
+    }
+
+    /// same as `framebuffer` but lit pixels take the cabinet overlay color
+    pub fn framebuffer_with_overlay(&self) -> Vec<u8> {
+        self.render(true)
+    }
+
+    /// fnv-1a over `framebuffer`, stable across builds and platforms so it
+    /// can be stored as a golden value
+    pub fn screen_hash(&self) -> u64 {
+        self.framebuffer()
+            .iter()
+            .fold(0xcbf29ce484222325, |hash, &byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            })
+    }
+
+    fn render(&self, overlay: bool) -> Vec<u8> {
+        let config = &SPACE_INVADERS;
+        let flip = self.io.flip_screen();
+        let mut pixels = vec![0; config.screen_width * config.screen_height * 4];
+        let screen_bytes = config.screen_width * config.screen_height / 8;
+        for (i, addr) in config.vram().take(screen_bytes).enumerate() {
+            let byte = self.cpu.peek(addr as u16);
+            // the color is monocrome so each byte holds 8 pixel
+            for bit in 0..8 {
+                let (x, y) = pixel_position(config, i, bit, flip);
+
+                let offset = (y * config.screen_width + x) * 4;
+                if byte & (1 << bit) != 0 {
+                    let color = match overlay {
+                        true => overlay_color(x, y),
+                        false => WHITE,
+                    };
+                    pixels[offset..offset + 3].copy_from_slice(&color);
+                }
+                pixels[offset + 3] = 0xff;
+            }
+        }
+        pixels
+    }
+}
@@ -0,0 +1,73 @@
+use wasm_bindgen::prelude::*;
+
+use crate::cpu::Cpu8080;
+use crate::input::InputState;
+use crate::invaders::SpaceInvaders;
+use crate::video::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// handle the js side drives, one per canvas. the whole board, so the game
+/// gets its interrupts and reads the controls set here
+#[wasm_bindgen]
+pub struct Emulator {
+    machine: SpaceInvaders,
+    /// held until js changes it, latched at the start of every frame
+    input: InputState,
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            machine: SpaceInvaders::new(Cpu8080::new()),
+            input: InputState::default(),
+        }
+    }
+
+    pub fn load(&mut self, rom: &[u8]) -> Result<(), JsError> {
+        self.machine
+            .cpu
+            .load(rom)
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+
+    /// latches the controls and runs to the next vblank, call once per
+    /// animation frame
+    pub fn run_frame(&mut self) {
+        self.machine.io.update(&mut self.input);
+        self.machine.run_frame();
+    }
+
+    /// the buttons held down, laid out as in `InputState::to_bits`: coin,
+    /// then start, left, right and fire of each player
+    pub fn set_input(&mut self, bits: u16) {
+        self.input = InputState::from_bits(bits);
+    }
+
+    pub fn step(&mut self) {
+        self.machine.step();
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.machine.cpu.pc
+    }
+
+    pub fn width(&self) -> usize {
+        SCREEN_WIDTH
+    }
+
+    pub fn height(&self) -> usize {
+        SCREEN_HEIGHT
+    }
+
+    /// 224x256 rgba pixels, ready for `ImageData`
+    pub fn framebuffer(&self) -> Vec<u8> {
+        self.machine.framebuffer()
+    }
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,48 @@
+// build with: wasm-pack build --target web --out-dir web/pkg -- --features wasm
+import init, { Emulator } from "./pkg/intel_8080_emu.js";
+
+// bits of `Emulator.set_input`, the same keys as the desktop build
+const KEYS = {
+  KeyC: 1 << 0,
+  Enter: 1 << 1,
+  ArrowLeft: 1 << 2,
+  ArrowRight: 1 << 3,
+  Space: 1 << 4,
+  Digit2: 1 << 5,
+  KeyA: 1 << 6,
+  KeyD: 1 << 7,
+  KeyW: 1 << 8,
+};
+
+async function main() {
+  await init();
+
+  const rom = new Uint8Array(await (await fetch("./invaders")).arrayBuffer());
+  const emulator = new Emulator();
+  emulator.load(rom);
+
+  let input = 0;
+  const key = (down) => (event) => {
+    const bit = KEYS[event.code];
+    if (bit === undefined) return;
+    input = down ? input | bit : input & ~bit;
+    emulator.set_input(input);
+    event.preventDefault();
+  };
+  window.addEventListener("keydown", key(true));
+  window.addEventListener("keyup", key(false));
+
+  const canvas = document.getElementById("screen");
+  const ctx = canvas.getContext("2d");
+  const image = ctx.createImageData(emulator.width(), emulator.height());
+
+  const frame = () => {
+    emulator.run_frame();
+    image.data.set(emulator.framebuffer());
+    ctx.putImageData(image, 0, 0);
+    requestAnimationFrame(frame);
+  };
+  requestAnimationFrame(frame);
+}
+
+main();